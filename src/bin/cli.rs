@@ -0,0 +1,135 @@
+//! Command-line tools that need no frontend.
+//!
+//! `minesweeper-cli generate` lays out boards with [`Minesweeper::place_mines`] and prints
+//! them in the text or JSON format of [`minesweeper::layout`], for puzzle sets and test fixtures.
+//!
+//! [`Minesweeper::place_mines`]: minesweeper::Minesweeper::place_mines
+use std::env;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use minesweeper::layout::Layout;
+use minesweeper::mask::Mask;
+use minesweeper::{GameConfig, Pos};
+
+const USAGE: &str = "\
+Usage: minesweeper-cli generate [OPTIONS]
+
+Prints mine layouts: `.` is empty, `*` a mine, `2`-`9` a cell with several mines.
+
+Options:
+  --width N           board width [default: 10]
+  --height N          board height [default: 10]
+  --mines N           number of mines [default: 15]
+  --mines-per-cell N  most mines in one cell, from 1 to 9 [default: 1]
+  --shape FILE        board shape (ASCII art or PNG) instead of --width and --height
+  --seed N            seed of the first board [default: random]
+  --first-click X,Y   keep this cell and its neighbors free of mines
+  --no-guess          only boards solvable from the first click without guessing
+  --count N           number of boards, with consecutive seeds [default: 1]
+  --json              print one JSON object per line instead of text
+  -h, --help          print this help";
+
+struct Options {
+    config: GameConfig,
+    seed: u64,
+    first_click: Option<Pos>,
+    count: u64,
+    json: bool,
+}
+
+/// Reads the value following option `name`.
+fn value<T: FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> Result<T, String> {
+    let value = args.next().ok_or_else(|| format!("{name} needs a value"))?;
+    value.parse().map_err(|_| format!("invalid value for {name}: {value}"))
+}
+
+fn parse_pos(text: &str) -> Result<Pos, String> {
+    let (x, y) = text.split_once(',').ok_or_else(|| format!("expected X,Y, got {text}"))?;
+    match (x.trim().parse(), y.trim().parse()) {
+        (Ok(x), Ok(y)) => Ok(Pos::new(x, y)),
+        _ => Err(format!("expected X,Y, got {text}")),
+    }
+}
+
+/// Parses the options of `generate`; `Ok(None)` asks for the help text.
+fn parse_generate(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {config: GameConfig::default(), seed: rand::random(), first_click: None, count: 1, json: false};
+    let config = &mut options.config;
+    let (mut sized, mut shaped) = (false, false);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => (config.width, sized) = (value(&mut args, &arg)?, true),
+            "--height" => (config.height, sized) = (value(&mut args, &arg)?, true),
+            "--mines" => config.mine_count = value(&mut args, &arg)?,
+            "--mines-per-cell" => config.mines_per_cell = value(&mut args, &arg)?,
+            "--shape" => {
+                let path: String = value(&mut args, &arg)?;
+                let mask = Mask::load(&path).map_err(|e| format!("cannot read {path}: {e}"))?;
+                (config.width, config.height) = (mask.width(), mask.height());
+                config.mask = Some(mask);
+                shaped = true;
+            }
+            "--seed" => options.seed = value(&mut args, &arg)?,
+            "--first-click" => options.first_click = Some(parse_pos(&value::<String>(&mut args, &arg)?)?),
+            "--no-guess" => config.no_guess = true,
+            "--count" => options.count = value(&mut args, &arg)?,
+            "--json" => options.json = true,
+            "-h" | "--help" => return Ok(None),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    if sized && shaped {
+        return Err("--shape sets the size; it cannot be combined with --width or --height".to_string());
+    }
+    if !(1..=GameConfig::MAX_MINES_PER_CELL).contains(&config.mines_per_cell) {
+        return Err(format!("--mines-per-cell must be from 1 to {}", GameConfig::MAX_MINES_PER_CELL));
+    }
+    if config.no_guess && options.first_click.is_none() {
+        return Err("--no-guess needs --first-click".to_string());
+    }
+    Ok(Some(options))
+}
+
+fn generate(options: Options) -> Result<(), String> {
+    for i in 0..options.count {
+        let layout = Layout::generate(options.config.clone(), options.seed.wrapping_add(i), options.first_click)?;
+        if options.json {
+            println!("{}", layout.to_json());
+        } else {
+            if i > 0 {
+                println!();
+            }
+            print!("{}", layout.to_text());
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("generate") => parse_generate(args),
+        Some("-h" | "--help") => Ok(None),
+        Some(command) => Err(format!("unknown command {command}")),
+        None => Err("missing command".to_string()),
+    };
+    let options = match result {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match generate(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Terminal frontend for playing over SSH, built with the `tui` feature. It drives the same
+//! [`Minesweeper`] engine as the egui app, without a graphical stack.
+//!
+//! Keys: arrows, WASD or HJKL move the cursor; Space or Enter reveals, F flags, C chords,
+//! `?` shows a hint, R starts a new game, 1–3 pick a preset, U or Ctrl+Z undoes, Ctrl+Y redoes,
+//! Q or Esc quits. In terminals with mouse support, left click reveals, right click flags and
+//! middle click chords.
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use minesweeper::solver::Hint;
+use minesweeper::{Action, Cell, CellState, GameConfig, Minesweeper, Pos, Topology};
+
+/// Terminal columns per cell. Odd hex rows are shifted by one column, half a cell.
+const CELL_WIDTH: u16 = 2;
+/// Title and status lines above the board.
+const HEADER_ROWS: u16 = 2;
+/// Message and help lines below the board.
+const FOOTER_ROWS: u16 = 2;
+/// How often the clock is checked while no input arrives; the screen is only redrawn after
+/// input or when the shown time changes.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Raw mode, alternate screen and mouse capture, undone on drop so a panic leaves a usable
+/// terminal behind.
+struct Terminal;
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, event::EnableMouseCapture, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, event::DisableMouseCapture, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Tui {
+    game: Minesweeper,
+    cursor: Pos,
+    /// Top-left cell of the part of the board that fits the terminal.
+    scroll: Pos,
+    hint: Option<Hint>,
+}
+impl Tui {
+    fn new(config: GameConfig) -> Self {
+        let game = Minesweeper::new(config);
+        Self {cursor: game.grid.middle().unwrap_or_default(), game, scroll: Pos::default(), hint: None}
+    }
+    fn start(&mut self, config: GameConfig) {
+        *self = Self::new(config);
+    }
+    fn run(&mut self) -> io::Result<()> {
+        let mut out = io::stdout();
+        let (mut dirty, mut shown_secs) = (true, None);
+        loop {
+            let secs = self.game.timer.elapsed().as_secs();
+            if dirty || shown_secs != Some(secs) {
+                self.draw(&mut out)?;
+                (dirty, shown_secs) = (false, Some(secs));
+            }
+            if !event::poll(TICK)? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    if !self.key(key) {
+                        return Ok(());
+                    }
+                    dirty = true;
+                }
+                Event::Mouse(mouse) => dirty |= self.mouse(mouse)?,
+                Event::Resize(..) => dirty = true,
+                _ => {}
+            }
+        }
+    }
+    /// Handles a key press; returns `false` to quit.
+    fn key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if control => return false,
+            KeyCode::Char('z') if control => {
+                self.game.undo();
+            }
+            KeyCode::Char('y') if control => {
+                self.game.redo();
+            }
+            KeyCode::Char('u') => {
+                self.game.undo();
+            }
+            KeyCode::Left | KeyCode::Char('a' | 'h') => self.move_cursor(-1, 0),
+            KeyCode::Right | KeyCode::Char('d' | 'l') => self.move_cursor(1, 0),
+            KeyCode::Up | KeyCode::Char('w' | 'k') => self.move_cursor(0, -1),
+            KeyCode::Down | KeyCode::Char('s' | 'j') => self.move_cursor(0, 1),
+            KeyCode::Char(' ') | KeyCode::Enter => self.play(Action::Reveal, self.cursor),
+            KeyCode::Char('f') => self.play(Action::Flag, self.cursor),
+            KeyCode::Char('c') => self.play(Action::Chord, self.cursor),
+            KeyCode::Char('?') => self.hint = self.game.hint(),
+            KeyCode::Char('r') | KeyCode::F(2) => self.start(self.game.config.clone()),
+            KeyCode::Char(digit @ '1'..='3') => {
+                let (_, config) = GameConfig::presets().into_iter().nth(digit as usize - '1' as usize).expect("three presets");
+                self.start(config);
+            }
+            _ => {}
+        }
+        true
+    }
+    /// Handles a click; returns whether it was on the board.
+    fn mouse(&mut self, mouse: MouseEvent) -> io::Result<bool> {
+        let MouseEventKind::Down(button) = mouse.kind else {
+            return Ok(false);
+        };
+        let Some(pos) = self.cell_at(mouse.column, mouse.row)? else {
+            return Ok(false);
+        };
+        self.cursor = pos;
+        match button {
+            MouseButton::Left => self.play(Action::Reveal, pos),
+            MouseButton::Right => self.play(Action::Flag, pos),
+            MouseButton::Middle => self.play(Action::Chord, pos),
+        }
+        Ok(true)
+    }
+    /// Plays like a click: reveal and flag turn into chords on revealed numbers, and chords
+    /// elsewhere are ignored rather than recorded as wasted clicks.
+    fn play(&mut self, action: Action, pos: Pos) {
+        self.hint = None;
+        match action {
+            Action::Reveal => self.game.primary_click(pos),
+            Action::Flag => self.game.secondary_click(pos),
+            action if self.game.grid[pos].state == CellState::Revealed => self.game.play(action, pos),
+            _ => {}
+        }
+    }
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        if let Some(pos) = self.game.grid.step(self.cursor, dx, dy) {
+            self.cursor = pos;
+        }
+    }
+    /// Cells that fit the terminal, as `(columns, rows)`.
+    fn viewport() -> io::Result<(usize, usize)> {
+        let (width, height) = terminal::size()?;
+        let columns = width.saturating_sub(1) / CELL_WIDTH;
+        let rows = height.saturating_sub(HEADER_ROWS + FOOTER_ROWS);
+        Ok((columns.max(1) as usize, rows.max(1) as usize))
+    }
+    /// First terminal column of the board; the board is centered when it fits.
+    fn left(&self) -> io::Result<u16> {
+        let (width, _) = terminal::size()?;
+        let board = self.game.grid.width() as u16 * CELL_WIDTH + 1;
+        Ok(width.saturating_sub(board) / 2)
+    }
+    fn cell_at(&self, column: u16, row: u16) -> io::Result<Option<Pos>> {
+        let (Some(row), Some(column)) = (row.checked_sub(HEADER_ROWS), column.checked_sub(self.left()?)) else {
+            return Ok(None);
+        };
+        let y = row as usize + self.scroll.y;
+        let shift = (self.game.config.topology == Topology::Hex && y % 2 == 1) as u16;
+        let Some(column) = column.checked_sub(shift) else {
+            return Ok(None);
+        };
+        Ok(self.game.grid.pos(column as usize / CELL_WIDTH as usize + self.scroll.x, y))
+    }
+    /// Scrolls just enough to keep the cursor in view.
+    fn follow_cursor(&mut self, (columns, rows): (usize, usize)) {
+        let follow = |scroll: usize, cursor: usize, len: usize| {
+            if cursor < scroll {cursor} else if cursor >= scroll + len {cursor + 1 - len} else {scroll}
+        };
+        self.scroll = Pos::new(follow(self.scroll.x, self.cursor.x, columns), follow(self.scroll.y, self.cursor.y, rows));
+    }
+    /// Draws over the previous frame line by line, clearing only what the new frame leaves
+    /// behind, so nothing flickers.
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let viewport = Self::viewport()?;
+        self.follow_cursor(viewport);
+        let left = self.left()? as usize;
+        let clear = terminal::Clear(terminal::ClearType::UntilNewLine);
+        queue!(out, cursor::MoveTo(0, 0), SetAttribute(Attribute::Bold), Print("MINESWEEPER  "), SetAttribute(Attribute::Reset))?;
+        queue!(out, Print(config_name(&self.game.config)), clear)?;
+        let mut status = format!("Mines: {:3}   Time: {:3}", self.game.mines_remaining(), self.game.timer.elapsed().as_secs());
+        if self.game.config.lives > 1 {
+            status += &format!("   Lives: {}", self.game.lives_left());
+        }
+        if self.game.no_guess_failed {
+            status += "   (no guess-free board found, may need guessing)";
+        }
+        queue!(out, cursor::MoveTo(0, 1), Print(status), clear)?;
+        let hint = self.hint.map(|hint| match hint {
+            Hint::Safe(pos) => (pos, Color::Blue),
+            Hint::Mine(pos) => (pos, Color::Red),
+            Hint::Guess(pos, _) => (pos, Color::DarkYellow),
+        });
+        let (columns, rows) = viewport;
+        let grid = &self.game.grid;
+        for y in self.scroll.y..(self.scroll.y + rows).min(grid.height()) {
+            let shift = (self.game.config.topology == Topology::Hex && y % 2 == 1) as usize;
+            queue!(out, cursor::MoveTo(0, HEADER_ROWS + (y - self.scroll.y) as u16), Print(" ".repeat(left + shift)))?;
+            for x in self.scroll.x..(self.scroll.x + columns).min(grid.width()) {
+                let pos = Pos::new(x, y);
+                if !grid.contains(pos) {
+                    queue!(out, Print("  "))?;
+                    continue;
+                }
+                let (glyph, color) = glyph(&grid[pos]);
+                if let Some((_, background)) = hint.filter(|&(hint, _)| hint == pos) {
+                    queue!(out, SetBackgroundColor(background))?;
+                } else if grid[pos].exploded {
+                    queue!(out, SetBackgroundColor(Color::DarkRed))?;
+                }
+                if pos == self.cursor {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(out, SetForegroundColor(color), Print(glyph), SetAttribute(Attribute::Reset), ResetColor)?;
+            }
+            queue!(out, clear)?;
+        }
+        let board_bottom = HEADER_ROWS + rows.min(grid.height()) as u16;
+        let message = if self.game.game_over {
+            "You hit a mine! R: new game".to_string()
+        } else if self.game.game_won {
+            format!("You won in {:.3} s! R: new game", self.game.timer.elapsed().as_secs_f64())
+        } else {
+            String::new()
+        };
+        queue!(out, cursor::MoveTo(0, board_bottom), SetAttribute(Attribute::Bold), Print(message), SetAttribute(Attribute::Reset), clear)?;
+        queue!(
+            out,
+            cursor::MoveTo(0, board_bottom + 1),
+            SetForegroundColor(Color::DarkGrey),
+            Print("arrows/wasd/hjkl move · space reveal · f flag · c chord · ? hint · u undo · r new · 1-3 presets · q quit"),
+            ResetColor,
+            clear
+        )?;
+        if board_bottom + 2 < terminal::size()?.1 {
+            queue!(out, cursor::MoveTo(0, board_bottom + 2), terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        }
+        out.flush()
+    }
+}
+
+/// The two terminal columns for a cell and their color.
+fn glyph(cell: &Cell) -> (String, Color) {
+    match cell.state {
+        CellState::Hidden => ("· ".to_string(), Color::DarkGrey),
+        CellState::Questioned => ("? ".to_string(), Color::Yellow),
+        CellState::Flagged if cell.flags > 1 => (format!("{} ", cell.flags), Color::Red),
+        CellState::Flagged => ("⚑ ".to_string(), Color::Red),
+        CellState::Revealed if cell.is_mine() => ("* ".to_string(), Color::White),
+        CellState::Revealed if cell.adjacent_mines == 0 => ("  ".to_string(), Color::Reset),
+        CellState::Revealed => {
+            let color = match cell.adjacent_mines {
+                1 => Color::Blue,
+                2 => Color::Green,
+                3 => Color::Red,
+                4 => Color::DarkBlue,
+                5 => Color::DarkRed,
+                6 => Color::Cyan,
+                7 => Color::Magenta,
+                8 => Color::Grey,
+                _ => Color::White,
+            };
+            (format!("{:<2}", cell.adjacent_mines), color)
+        }
+    }
+}
+
+fn config_name(config: &GameConfig) -> String {
+    GameConfig::presets().into_iter()
+        .find(|(_, preset)| preset == config)
+        .map_or_else(|| format!("Custom ({}×{}, {} mines)", config.width, config.height, config.mine_count), |(name, _)| name.to_string())
+}
+
+fn main() -> io::Result<()> {
+    let _terminal = Terminal::enter()?;
+    Tui::new(GameConfig::default()).run()
+}
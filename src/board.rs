@@ -0,0 +1,399 @@
+//! Rectangular cell storage shared by the rules, the solver and the frontends.
+//!
+//! A [`Board`] keeps its cells in one row-major `Vec` and is indexed by [`Pos`]. Positions
+//! handed out by the board itself ([`Board::pos`], [`Board::positions`], [`Board::neighbors`])
+//! are always inside it and never holes; indexing outside the rectangle panics like slice
+//! indexing does.
+use std::ops::{Index, IndexMut};
+use std::slice;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mask::Mask;
+
+/// A cell position, `x` counted from the left and `y` from the top.
+///
+/// Serializes as an `[x, y]` pair.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "(usize, usize)", into = "(usize, usize)")]
+pub struct Pos {pub x: usize, pub y: usize}
+impl Pos {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self {x, y}
+    }
+}
+impl From<(usize, usize)> for Pos {
+    fn from((x, y): (usize, usize)) -> Self {
+        Self {x, y}
+    }
+}
+impl From<Pos> for (usize, usize) {
+    fn from(pos: Pos) -> Self {
+        (pos.x, pos.y)
+    }
+}
+
+/// How cells are shaped and which cells touch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// Square cells with eight neighbors.
+    #[default]
+    Square,
+    /// Pointy-top hexagons with six neighbors. Odd rows are shifted right by half a cell.
+    Hex,
+}
+impl Topology {
+    /// Steps from a cell in an even or odd row to the cell `(dx, dy)` away.
+    fn distance(self, odd_row: bool, dx: isize, dy: isize) -> isize {
+        match self {
+            Self::Square => dx.abs().max(dy.abs()),
+            Self::Hex => {
+                // Axial coordinates: undo the half-cell shift of odd rows.
+                let y = odd_row as isize + dy;
+                let dq = dx - (y - y.rem_euclid(2)) / 2;
+                (dq.abs() + dy.abs() + (dq + dy).abs()) / 2
+            }
+        }
+    }
+}
+
+/// Which cells count as a cell's neighbors: the ones its number counts, that are kept free
+/// around the first click, and that chording and flood fill open.
+///
+/// Offsets are `(dx, dy)` column and row steps. On hex boards, [`VonNeumann`](Self::VonNeumann),
+/// [`Knight`](Self::Knight) and [`Custom`](Self::Custom) apply them as is, so they follow the
+/// zigzag of the shifted rows.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Neighborhood {
+    /// The touching cells: eight on square boards, six on hex boards.
+    #[default]
+    Adjacent,
+    /// The four cells left, right, above and below.
+    VonNeumann,
+    /// Every cell up to two steps away: the surrounding 5×5 square, or two hex rings.
+    Radius2,
+    /// The eight cells a chess knight can jump to.
+    Knight,
+    /// Any offsets within [`MAX_REACH`](Self::MAX_REACH) in both directions. `(0, 0)`,
+    /// duplicates and offsets reaching further are ignored.
+    Custom(Vec<(isize, isize)>),
+}
+impl Neighborhood {
+    /// How far a neighborhood may reach in either direction.
+    pub const MAX_REACH: isize = 2;
+    /// Neighbor offsets of a cell in an even or odd row under `topology`, in row order.
+    pub fn offsets(&self, topology: Topology, odd_row: bool) -> Vec<(isize, isize)> {
+        let reach = -Self::MAX_REACH..=Self::MAX_REACH;
+        let window = reach.clone()
+            .flat_map(|dy| reach.clone().map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0));
+        match self {
+            Self::Adjacent => window.filter(|&(dx, dy)| topology.distance(odd_row, dx, dy) == 1).collect(),
+            Self::Radius2 => window.filter(|&(dx, dy)| topology.distance(odd_row, dx, dy) <= 2).collect(),
+            Self::VonNeumann => vec![(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Self::Knight => vec![(-1, -2), (1, -2), (-2, -1), (2, -1), (-2, 1), (2, 1), (-1, 2), (1, 2)],
+            Self::Custom(offsets) => window.filter(|offset| offsets.contains(offset)).collect(),
+        }
+    }
+}
+
+/// A `width`×`height` grid of `T` in flat row-major storage.
+///
+/// A [`Mask`] can punch holes into the rectangle. Holes still store a value, reachable through
+/// indexing and [`rows`](Self::rows), but are skipped by everything else: they are not
+/// [`contain`](Self::contains)ed, iterated or anyone's neighbor.
+///
+/// Serializes as a list of rows, the same shape as a `Vec<Vec<T>>`. The topology, neighborhood,
+/// wrapping and mask are not serialized; a deserialized board is a plain [`Topology::Square`]
+/// rectangle until [`with_topology`](Self::with_topology),
+/// [`with_neighborhood`](Self::with_neighborhood), [`wrapping`](Self::wrapping) and
+/// [`masked`](Self::masked) are applied.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Vec<T>>", into = "Vec<Vec<T>>")]
+#[serde(bound(serialize = "T: Clone + Serialize", deserialize = "T: Deserialize<'de>"))]
+pub struct Board<T> {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    neighborhood: Neighborhood,
+    /// Neighbor offsets for even and odd rows. Shared with [`Neighbors`].
+    offsets: [Arc<[(isize, isize)]>; 2],
+    wrap: bool,
+    /// Which cells exist, in row order; `None` for a full rectangle. Shared with [`Neighbors`].
+    mask: Option<Arc<[bool]>>,
+    cells: Vec<T>,
+}
+impl<T: Clone> Board<T> {
+    /// A square-celled board with every cell set to `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self::from_cells(width, height, vec![value; width * height])
+    }
+}
+impl<T> Board<T> {
+    fn from_cells(width: usize, height: usize, cells: Vec<T>) -> Self {
+        Self {
+            width,
+            height,
+            topology: Topology::default(),
+            neighborhood: Neighborhood::default(),
+            offsets: Default::default(),
+            wrap: false,
+            mask: None,
+            cells,
+        }.with_offsets()
+    }
+    /// A board with each cell computed from its position, in row order.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        let cells = (0..height).flat_map(|y| (0..width).map(move |x| Pos::new(x, y))).map(&mut f).collect();
+        Self::from_cells(width, height, cells)
+    }
+    /// The same cells with `topology`'s shape.
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self {topology, ..self}.with_offsets()
+    }
+    /// The same cells with `neighborhood` deciding which cells are neighbors.
+    pub fn with_neighborhood(self, neighborhood: Neighborhood) -> Self {
+        Self {neighborhood, ..self}.with_offsets()
+    }
+    fn with_offsets(self) -> Self {
+        let offsets = [false, true].map(|odd_row| self.neighborhood.offsets(self.topology, odd_row).into());
+        Self {offsets, ..self}
+    }
+    /// The same cells, with opposite edges adjacent if `wrap` is set (a torus).
+    ///
+    /// Hex boards need an even height to wrap vertically; with an odd height the top and
+    /// bottom rows are both unshifted and adjacency across that edge is not symmetric.
+    pub fn wrapping(self, wrap: bool) -> Self {
+        Self {wrap, ..self}
+    }
+    /// The same cells with the holes of `mask`, or none for `None`. Cells outside the mask's
+    /// rectangle become holes.
+    pub fn masked(self, mask: Option<&Mask>) -> Self {
+        let mask = mask.map(|mask| self.positions_unmasked().map(|pos| mask.contains(pos)).collect());
+        Self {mask, ..self}
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+    pub fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+    /// Whether neighbors wrap around the edges.
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+    /// Number of cells, not counting holes.
+    pub fn len(&self) -> usize {
+        self.mask.as_ref().map_or(self.cells.len(), |mask| mask.iter().filter(|&&cell| cell).count())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Whether `pos` is on the board and not a hole.
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x < self.width && pos.y < self.height && self.exists(pos.y * self.width + pos.x)
+    }
+    fn exists(&self, index: usize) -> bool {
+        self.mask.as_ref().is_none_or(|mask| mask[index])
+    }
+    /// `(x, y)` as a position, if it is on the board.
+    pub fn pos(&self, x: usize, y: usize) -> Option<Pos> {
+        let pos = Pos::new(x, y);
+        self.contains(pos).then_some(pos)
+    }
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self.cells[pos.y * self.width + pos.x])
+    }
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.contains(pos).then(|| &mut self.cells[pos.y * self.width + pos.x])
+    }
+    fn positions_unmasked(&self) -> impl Iterator<Item = Pos> + use<T> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Pos::new(x, y)))
+    }
+    /// Every position in row order.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + use<T> {
+        let mask = self.mask.clone();
+        let width = self.width;
+        self.positions_unmasked().filter(move |pos| mask.as_ref().is_none_or(|mask| mask[pos.y * width + pos.x]))
+    }
+    /// All cells in row order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter().enumerate().filter(|&(i, _)| self.exists(i)).map(|(_, cell)| cell)
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        let mask = self.mask.as_deref();
+        self.cells.iter_mut().enumerate().filter(move |&(i, _)| mask.is_none_or(|mask| mask[i])).map(|(_, cell)| cell)
+    }
+    /// All cells with their positions, in row order.
+    pub fn enumerate(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().map(|pos| (pos, &self.cells[pos.y * self.width + pos.x]))
+    }
+    /// The rows from top to bottom, holes included.
+    pub fn rows(&self) -> slice::Chunks<'_, T> {
+        self.cells.chunks(self.width.max(1))
+    }
+    /// The on-board neighbors of `pos` under the board's topology and [`Neighborhood`]. The
+    /// iterator does not borrow the board, so cells can be modified while walking it.
+    pub fn neighbors(&self, pos: Pos) -> Neighbors {
+        Neighbors {
+            center: pos,
+            width: self.width,
+            height: self.height,
+            wrap: self.wrap,
+            mask: self.mask.clone(),
+            offsets: self.offsets[pos.y % 2].clone(),
+            next: 0,
+        }
+    }
+    /// The cell closest to the middle of the board, if there is any cell.
+    pub fn middle(&self) -> Option<Pos> {
+        let (x, y) = (self.width / 2, self.height / 2);
+        self.positions().min_by_key(|pos| pos.x.abs_diff(x) + pos.y.abs_diff(y))
+    }
+    /// The first cell reached from `pos` by repeatedly moving `(dx, dy)`, skipping holes and
+    /// wrapping around on wrapping boards. `None` if the edge comes first. For moving a cursor.
+    pub fn step(&self, pos: Pos, dx: isize, dy: isize) -> Option<Pos> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let (mut x, mut y) = (pos.x as isize, pos.y as isize);
+        for _ in 0..width.max(height) {
+            x += dx;
+            y += dy;
+            if self.wrap {
+                x = x.rem_euclid(width);
+                y = y.rem_euclid(height);
+            } else if !(0..width).contains(&x) || !(0..height).contains(&y) {
+                return None;
+            }
+            if let Some(pos) = self.pos(x as usize, y as usize) {
+                return Some(pos);
+            }
+        }
+        None
+    }
+    /// A board of the same size and shape with `f` applied to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Board<U> {
+        Board {
+            width: self.width,
+            height: self.height,
+            topology: self.topology,
+            neighborhood: self.neighborhood.clone(),
+            offsets: self.offsets.clone(),
+            wrap: self.wrap,
+            mask: self.mask.clone(),
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+impl<T> Index<Pos> for Board<T> {
+    type Output = T;
+    fn index(&self, pos: Pos) -> &T {
+        assert!(pos.x < self.width && pos.y < self.height, "{pos:?} is outside the {}×{} board", self.width, self.height);
+        &self.cells[pos.y * self.width + pos.x]
+    }
+}
+impl<T> IndexMut<Pos> for Board<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        assert!(pos.x < self.width && pos.y < self.height, "{pos:?} is outside the {}×{} board", self.width, self.height);
+        &mut self.cells[pos.y * self.width + pos.x]
+    }
+}
+impl<T> TryFrom<Vec<Vec<T>>> for Board<T> {
+    type Error = String;
+    fn try_from(rows: Vec<Vec<T>>) -> Result<Self, String> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("board rows differ in length".to_string());
+        }
+        Ok(Self::from_cells(width, height, rows.into_iter().flatten().collect()))
+    }
+}
+impl<T: Clone> From<Board<T>> for Vec<Vec<T>> {
+    fn from(board: Board<T>) -> Self {
+        board.rows().map(<[T]>::to_vec).collect()
+    }
+}
+
+/// Iterator returned by [`Board::neighbors`].
+#[derive(Clone, Debug)]
+pub struct Neighbors {
+    center: Pos,
+    width: usize,
+    height: usize,
+    wrap: bool,
+    mask: Option<Arc<[bool]>>,
+    offsets: Arc<[(isize, isize)]>,
+    next: usize,
+}
+impl Neighbors {
+    /// Where `(dx, dy)` lands from the center on a wrapping board.
+    fn wrapped(&self, (dx, dy): (isize, isize)) -> Pos {
+        let x = (self.center.x as isize + dx).rem_euclid(self.width as isize) as usize;
+        let y = (self.center.y as isize + dy).rem_euclid(self.height as isize) as usize;
+        Pos::new(x, y)
+    }
+}
+impl Iterator for Neighbors {
+    type Item = Pos;
+    fn next(&mut self) -> Option<Pos> {
+        // On wrapping boards narrower or shorter than a neighborhood, offsets can land on the
+        // center or on the same cell twice.
+        let span = 2 * Neighborhood::MAX_REACH as usize + 1;
+        let small = self.wrap && (self.width < span || self.height < span);
+        while let Some(&offset) = self.offsets.get(self.next) {
+            self.next += 1;
+            let pos = if self.wrap {
+                let pos = self.wrapped(offset);
+                if small && (pos == self.center || self.offsets[..self.next - 1].iter().any(|&seen| self.wrapped(seen) == pos)) {
+                    continue;
+                }
+                pos
+            } else if let (Some(x), Some(y)) = (self.center.x.checked_add_signed(offset.0), self.center.y.checked_add_signed(offset.1))
+                && x < self.width && y < self.height {
+                Pos::new(x, y)
+            } else {
+                continue;
+            };
+            if self.mask.as_ref().is_none_or(|mask| mask[pos.y * self.width + pos.x]) {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn small_wrapping_boards_list_each_neighbor_once() {
+        for (width, height) in [(1, 5), (2, 2), (2, 3), (4, 4), (5, 5)] {
+            for neighborhood in [Neighborhood::Adjacent, Neighborhood::Radius2, Neighborhood::Knight] {
+                let board = Board::new(width, height, ()).with_neighborhood(neighborhood).wrapping(true);
+                for pos in board.positions() {
+                    let neighbors: Vec<Pos> = board.neighbors(pos).collect();
+                    let unique: HashSet<Pos> = neighbors.iter().copied().collect();
+                    assert_eq!(unique.len(), neighbors.len(), "{width}x{height} at {pos:?}");
+                    assert!(!unique.contains(&pos), "{width}x{height} at {pos:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wide_wrapping_boards_keep_full_neighborhoods() {
+        let board = Board::new(5, 5, ()).with_neighborhood(Neighborhood::Radius2).wrapping(true);
+        assert_eq!(board.neighbors(Pos::new(0, 0)).count(), 24);
+    }
+}
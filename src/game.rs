@@ -0,0 +1,844 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand::seq::index::sample;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::board::{Board, Neighborhood, Pos, Topology};
+use crate::mask::Mask;
+use crate::solver::{self, Hint};
+
+/// What the player currently sees on a cell.
+///
+/// `Questioned` is a reminder the player can put on a hidden cell; the rules treat it exactly
+/// like `Hidden`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CellState {#[default] Hidden, Revealed, Flagged, Questioned}
+impl CellState {
+    /// `Hidden` or `Questioned`: not opened and not flagged.
+    pub fn is_hidden(self) -> bool {
+        matches!(self, Self::Hidden | Self::Questioned)
+    }
+    fn is_default(&self) -> bool {
+        *self == Self::Hidden
+    }
+}
+
+/// A single board cell.
+///
+/// Fields holding their default are left out of the JSON, so saves of large boards stay small.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cell {
+    /// Mines on the cell, at most [`GameConfig::mines_per_cell`].
+    #[serde(alias = "is_mine", deserialize_with = "deserialize_mines", skip_serializing_if = "is_zero")]
+    pub mines: u8,
+    #[serde(skip_serializing_if = "CellState::is_default")]
+    pub state: CellState,
+    /// Flags the player put on the cell; nonzero exactly while `state` is `Flagged`.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub flags: u8,
+    /// Total mines on the neighbors; only meaningful for non-mine cells once mines are placed.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub adjacent_mines: u8,
+    /// The player opened this mine and lost a life; it stays flagged for the rest of the game.
+    #[serde(skip_serializing_if = "is_false")]
+    pub exploded: bool,
+    /// Shown when the game was lost rather than opened by the player.
+    #[serde(skip_serializing_if = "is_false")]
+    pub exposed: bool,
+}
+impl Cell {
+    pub fn is_mine(&self) -> bool {
+        self.mines > 0
+    }
+}
+fn is_zero(n: &u8) -> bool {
+    *n == 0
+}
+fn is_false(b: &bool) -> bool {
+    !b
+}
+/// Reads a mine count, or the `is_mine` flag of saves from before multi-mine cells.
+fn deserialize_mines<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Mines {Flag(bool), Count(u8)}
+    Ok(match Mines::deserialize(deserializer)? {
+        Mines::Flag(is_mine) => is_mine as u8,
+        Mines::Count(mines) => mines,
+    })
+}
+
+/// Board dimensions, mine count and generation options.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Total number of mines, counting every mine of a multi-mine cell.
+    pub mine_count: usize,
+    /// Most mines a single cell can hold, from 1 (classic Minesweeper) to
+    /// [`MAX_MINES_PER_CELL`](Self::MAX_MINES_PER_CELL). Numbers count mines rather than mined
+    /// cells, and flags cycle from 1 up to this many per cell.
+    pub mines_per_cell: u8,
+    /// Mines the player may hit before losing; 1 is classic Minesweeper. A hit that is not the
+    /// last one only costs a life, and the mine is flagged.
+    pub lives: u32,
+    /// Put a question mark between the last flag and hidden when cycling a cell with secondary
+    /// clicks. Missing from older saves and replays, whose flag cycle had no question mark.
+    #[serde(default)]
+    pub question_marks: bool,
+    /// Only generate layouts that can be cleared from the first click by pure deduction.
+    pub no_guess: bool,
+    pub topology: Topology,
+    /// Which cells count as neighbors.
+    pub neighborhood: Neighborhood,
+    /// Opposite edges touch, so every cell has a full neighborhood.
+    pub wrap: bool,
+    /// Board shape; `width` and `height` must match its size. `None` is the full rectangle.
+    pub mask: Option<Mask>,
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {width: 10, height: 10, mine_count: 15, mines_per_cell: 1, lives: 1, question_marks: true, no_guess: false, topology: Topology::Square, neighborhood: Neighborhood::Adjacent, wrap: false, mask: None}
+    }
+}
+impl GameConfig {
+    /// Largest [`mines_per_cell`](Self::mines_per_cell): one digit in the text layout format,
+    /// and even the widest neighborhood then counts fewer than 256 mines.
+    pub const MAX_MINES_PER_CELL: u8 = 9;
+    /// The classic difficulty levels, with display names.
+    pub fn presets() -> [(&'static str, Self); 3] {
+        [
+            ("Beginner (9×9, 10 mines)", Self {width: 9, height: 9, mine_count: 10, ..Self::default()}),
+            ("Intermediate (16×16, 40 mines)", Self {width: 16, height: 16, mine_count: 40, ..Self::default()}),
+            ("Expert (30×16, 99 mines)", Self {width: 30, height: 16, mine_count: 99, ..Self::default()}),
+        ]
+    }
+    /// Number of cells on the board, not counting holes.
+    pub fn cell_count(&self) -> usize {
+        self.mask.as_ref().map_or(self.width * self.height, Mask::cell_count)
+    }
+    /// Most mines [`Minesweeper::place_mines`] can fit: every slot outside the first click's
+    /// neighborhood.
+    pub fn max_mines(&self) -> usize {
+        let reserved = self.neighborhood.offsets(self.topology, false).len() + 1;
+        self.cell_count().saturating_sub(reserved) * self.mines_per_cell.max(1) as usize
+    }
+    /// An empty board of this size and shape.
+    fn empty_grid(&self) -> Board<Cell> {
+        self.board(Cell::default())
+    }
+    /// A board of this size and shape with `value` in every cell.
+    pub(crate) fn board<T: Clone>(&self, value: T) -> Board<T> {
+        Board::new(self.width, self.height, value)
+            .with_topology(self.topology)
+            .with_neighborhood(self.neighborhood.clone())
+            .wrapping(self.wrap)
+            .masked(self.mask.as_ref())
+    }
+}
+
+/// Play time of a game: runs from the first move until the game ends.
+///
+/// Serializes as the elapsed [`Duration`]; a deserialized timer is stopped.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(into = "Duration", from = "Duration")]
+pub struct Timer {elapsed: Duration, running_since: Option<Instant>}
+impl From<Timer> for Duration {
+    fn from(timer: Timer) -> Self {
+        timer.elapsed()
+    }
+}
+impl From<Duration> for Timer {
+    fn from(elapsed: Duration) -> Self {
+        Self {elapsed, running_since: None}
+    }
+}
+impl Timer {
+    /// Starts or resumes the timer; does nothing if it is already running.
+    pub fn start(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+    /// Pauses the timer, keeping the time accumulated so far.
+    pub fn stop(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed += since.elapsed();
+        }
+    }
+    /// `true` between the first move and the end of the game.
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+    /// Total time accumulated, including the current run.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// A kind of player input, as recorded in [`Minesweeper::moves`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    /// Primary click on a hidden or flagged cell.
+    Reveal,
+    /// Secondary click on a hidden or flagged cell.
+    Flag,
+    /// Primary click on a revealed number: opens its neighbors if enough flags surround it.
+    Chord,
+    /// Secondary click on a revealed number: flags its neighbors if only mines can remain.
+    FlagChord,
+}
+
+/// One recorded input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub action: Action,
+    pub x: usize,
+    pub y: usize,
+    /// Game time when the input happened; zero for the first move.
+    pub time: Duration,
+    /// Whether the input changed the board or the game status; a question mark counts.
+    #[serde(default = "effective_default")]
+    pub effective: bool,
+}
+impl Move {
+    pub fn pos(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
+}
+fn effective_default() -> bool {
+    true
+}
+
+/// Game-wide state that moves change besides the cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Status {game_over: bool, game_won: bool, first_click: bool, lives_lost: u32}
+
+/// One move on the undo or redo stack: the cells it changed, as `(pos, before, after)`, and
+/// the status around it. `moves` is the length of [`Minesweeper::moves`] before the move.
+#[derive(Clone, Debug)]
+struct Step {
+    m: Move,
+    moves: usize,
+    cells: Vec<(Pos, Cell, Cell)>,
+    before: Status,
+    after: Status,
+}
+
+/// How many layouts [`Minesweeper::place_mines`] tries before giving up on `no_guess`.
+const NO_GUESS_ATTEMPTS: usize = 2000;
+/// How long [`Minesweeper::place_mines`] searches for a no-guess layout. Generation runs on the
+/// first click, so this bounds the pause before the board opens.
+const NO_GUESS_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+/// A game in progress.
+///
+/// Mines are placed lazily on the first [`reveal_cell`](Self::reveal_cell) or
+/// [`toggle_flag`](Self::toggle_flag), keeping that cell and its neighbors free of mines.
+///
+/// The layout is fully determined by `seed`, the config and the first clicked cell: mines are
+/// drawn with [`rand::seq::index::sample`] from a [`ChaCha8Rng`] created with
+/// [`SeedableRng::seed_from_u64`]. ChaCha8 output is portable across platforms and releases, so a
+/// seed reproduces the same board on any machine running the same `rand` 0.9 series.
+///
+/// The whole game, including the mine layout and elapsed time, can be saved as JSON with
+/// [`to_json`](Self::to_json) / [`save`](Self::save) and restored with
+/// [`from_json`](Self::from_json) / [`load`](Self::load).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Minesweeper {
+    pub grid: Board<Cell>,
+    pub config: GameConfig,
+    pub game_over: bool,
+    pub game_won: bool,
+    /// `true` until the first move; mines are placed then unless `layout_fixed` is set.
+    pub first_click: bool,
+    /// Mines were laid out up front (see [`with_layout`](Self::with_layout)) rather than
+    /// generated on the first move.
+    #[serde(default)]
+    pub layout_fixed: bool,
+    /// Seed used by [`place_mines`](Self::place_mines).
+    pub seed: u64,
+    /// `config.no_guess` is set but [`place_mines`](Self::place_mines) found no layout solvable
+    /// without guessing, so this board may need guesses. Recorded as an ordinary game.
+    #[serde(default)]
+    pub no_guess_failed: bool,
+    /// Number of times [`hint`](Self::hint) suggested a move this game.
+    pub hints_used: u32,
+    /// Mines hit so far; the game is lost once this reaches `config.lives`.
+    #[serde(default)]
+    pub lives_lost: u32,
+    pub timer: Timer,
+    /// Every input made through [`play`](Self::play), in order. Undone moves are removed.
+    #[serde(default)]
+    pub moves: Vec<Move>,
+    /// Mine hits and finished games can be undone, and the game is never recorded in
+    /// statistics.
+    #[serde(default)]
+    pub practice: bool,
+    /// A move was taken back with [`undo`](Self::undo); such games are not recorded in
+    /// statistics.
+    #[serde(default)]
+    pub undone: bool,
+    /// Moves that [`undo`](Self::undo) can take back, oldest first. Not saved.
+    #[serde(skip)]
+    undo_stack: Vec<Step>,
+    /// Undone moves that [`redo`](Self::redo) can play again, most recently undone last.
+    #[serde(skip)]
+    redo_stack: Vec<Step>,
+}
+impl Minesweeper {
+    /// Creates an empty board with a random seed; mines are placed on the first move.
+    pub fn new(config: GameConfig) -> Self {
+        Self::with_seed(config, rand::random())
+    }
+    /// Creates an empty board whose layout is reproducible from `seed`.
+    pub fn with_seed(config: GameConfig, seed: u64) -> Self {
+        Self {
+            grid: config.empty_grid(),
+            config,
+            game_over: false,
+            game_won: false,
+            first_click: true,
+            layout_fixed: false,
+            seed,
+            no_guess_failed: false,
+            hints_used: 0,
+            lives_lost: 0,
+            timer: Timer::default(),
+            moves: Vec::new(),
+            practice: false,
+            undone: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+    /// Creates a board with mines exactly at `mines`; the first move does not generate
+    /// anything and may hit a mine. A position listed several times holds that many mines.
+    /// `config.mine_count` is set to the number of mines placed.
+    ///
+    /// # Panics
+    /// Panics if a position is outside the board.
+    pub fn with_layout(config: GameConfig, mines: &[Pos]) -> Self {
+        let mut game = Self::with_seed(config, 0);
+        for &pos in mines {
+            game.grid[pos].mines += 1;
+        }
+        game.config.mine_count = game.grid.iter().map(|cell| cell.mines as usize).sum();
+        game.count_all_adjacent_mines();
+        game.layout_fixed = true;
+        game
+    }
+    /// Positions of all mines in row order, repeated for cells holding several.
+    pub fn mine_positions(&self) -> Vec<Pos> {
+        self.grid.enumerate().flat_map(|(pos, cell)| std::iter::repeat_n(pos, cell.mines as usize)).collect()
+    }
+    /// Serializes the full game state as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("game state is always serializable")
+    }
+    /// Restores a game written by [`to_json`](Self::to_json). The timer resumes if the game
+    /// was still in progress.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let mut game: Self = serde_json::from_str(json)?;
+        if (game.grid.width(), game.grid.height()) != (game.config.width, game.config.height) {
+            return Err(serde::de::Error::custom("grid size does not match config"));
+        }
+        if game.config.mask.as_ref().is_some_and(|mask| (mask.width(), mask.height()) != (game.config.width, game.config.height)) {
+            return Err(serde::de::Error::custom("mask size does not match config"));
+        }
+        game.grid = game.grid
+            .with_topology(game.config.topology)
+            .with_neighborhood(game.config.neighborhood.clone())
+            .wrapping(game.config.wrap)
+            .masked(game.config.mask.as_ref());
+        for cell in game.grid.iter_mut() {
+            // Saves from before multi-mine cells carry no flag counts.
+            if cell.state == CellState::Flagged && cell.flags == 0 {
+                cell.flags = 1;
+            }
+        }
+        if game.in_progress() {
+            game.timer.start();
+        }
+        Ok(game)
+    }
+    /// Writes the game to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+    /// Reads a game saved with [`save`](Self::save).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    /// `true` after the first move and before the game is won or lost.
+    pub fn in_progress(&self) -> bool {
+        !self.first_click && !self.game_over && !self.game_won
+    }
+    /// Starts a new game with the same config and a fresh random seed, keeping `practice`.
+    pub fn reset(&mut self) {
+        *self = Self {practice: self.practice, ..Self::new(self.config.clone())};
+    }
+    /// Places `config.mine_count` mines, chosen from `seed`, away from `safe` and its neighbors
+    /// and computes `adjacent_mines` for every cell.
+    ///
+    /// Every free cell offers `config.mines_per_cell` slots and mines are drawn among the slots,
+    /// so a cell ends up with anywhere from none to `mines_per_cell` mines. With one mine per
+    /// cell this is the classic layout.
+    ///
+    /// With `config.no_guess`, layouts are resampled from the same RNG stream until the solver
+    /// can clear the board from `safe` without guessing. If none is found within a fixed number
+    /// of attempts and a time limit (dense or very large boards), the first layout drawn from
+    /// the seed is used, [`no_guess_failed`](Self::no_guess_failed) is set and `false` is
+    /// returned. Because of the time limit, a slow machine may give up on a seed that a faster
+    /// one solves, but both then play the same layout.
+    ///
+    /// If there are fewer free slots than `config.mine_count`, the free slots are filled and
+    /// `config.mine_count` is lowered to match.
+    pub fn place_mines(&mut self, safe: Pos) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.no_guess_failed = false;
+        if !self.config.no_guess {
+            self.lay_mines(&mut rng, Some(safe));
+            return true;
+        }
+        let deadline = Instant::now() + NO_GUESS_TIME_LIMIT;
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            self.lay_mines(&mut rng, Some(safe));
+            if solver::solvable_from(self, safe, deadline) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+        }
+        self.lay_mines(&mut ChaCha8Rng::seed_from_u64(self.seed), Some(safe));
+        self.no_guess_failed = true;
+        false
+    }
+    /// Like [`place_mines`](Self::place_mines) with no cell kept free, for layouts generated
+    /// before anyone clicks. `config.no_guess` is ignored since there is no starting point.
+    /// The layout is then fixed: the first move does not generate it again.
+    pub fn place_mines_anywhere(&mut self) {
+        self.lay_mines(&mut ChaCha8Rng::seed_from_u64(self.seed), None);
+        self.layout_fixed = true;
+    }
+    fn lay_mines(&mut self, rng: &mut ChaCha8Rng, safe: Option<Pos>) {
+        for cell in self.grid.iter_mut() {
+            *cell = Cell::default();
+        }
+        let reserved: Vec<Pos> = safe.into_iter().flat_map(|safe| self.grid.neighbors(safe).chain([safe])).collect();
+        let space: Vec<Pos> = self.grid.positions().filter(|pos| !reserved.contains(pos)).collect();
+        let per_cell = self.config.mines_per_cell.max(1) as usize;
+        self.config.mine_count = self.config.mine_count.min(space.len() * per_cell);
+        for i in sample(rng, space.len() * per_cell, self.config.mine_count) {
+            self.grid[space[i / per_cell]].mines += 1;
+        }
+        self.count_all_adjacent_mines();
+    }
+    fn count_all_adjacent_mines(&mut self) {
+        for pos in self.grid.positions() {
+            if !self.grid[pos].is_mine() {
+                self.grid[pos].adjacent_mines = self.count_adjacent_mines(pos);
+            }
+        }
+    }
+    fn count_adjacent_mines(&self, pos: Pos) -> u8 {
+        self.grid.neighbors(pos).map(|n| self.grid[n].mines).sum()
+    }
+    /// Performs a player input and records it in `moves`. Inputs that changed the board or the
+    /// game status also go on the undo stack and clear the redo stack. Inputs after the game has
+    /// ended and inputs on holes are ignored.
+    pub fn play(&mut self, action: Action, pos: Pos) {
+        if self.game_over || self.game_won || !self.grid.contains(pos) {
+            return;
+        }
+        let time = self.timer.elapsed();
+        let (grid_before, status_before) = (self.grid.clone(), self.status());
+        match action {
+            Action::Reveal => {
+                if self.grid[pos].state != CellState::Flagged {
+                    self.reveal_cell(pos);
+                }
+                self.chord(pos);
+            }
+            Action::Flag => self.toggle_flag(pos),
+            Action::Chord => self.chord(pos),
+            Action::FlagChord => self.flag_neighbors(pos),
+        }
+        let cells: Vec<_> = self.grid.positions()
+            .filter(|&pos| grid_before[pos] != self.grid[pos])
+            .map(|pos| (pos, grid_before[pos], self.grid[pos]))
+            .collect();
+        let status = self.status();
+        let effective = !cells.is_empty() || status != status_before;
+        let m = Move {action, x: pos.x, y: pos.y, time, effective};
+        self.moves.push(m);
+        if !effective {
+            return;
+        }
+        self.undo_stack.push(Step {m, moves: self.moves.len() - 1, cells, before: status_before, after: status});
+        self.redo_stack.clear();
+    }
+    fn status(&self) -> Status {
+        Status {game_over: self.game_over, game_won: self.game_won, first_click: self.first_click, lives_lost: self.lives_lost}
+    }
+    /// Restores `status`, running the timer only while the game is in progress.
+    fn set_status(&mut self, status: Status) {
+        self.game_over = status.game_over;
+        self.game_won = status.game_won;
+        self.first_click = status.first_click;
+        self.lives_lost = status.lives_lost;
+        if self.in_progress() {
+            self.timer.start();
+        } else {
+            self.timer.stop();
+        }
+    }
+    /// Whether [`undo`](Self::undo) would take back a move. Outside `practice`, only moves of a
+    /// game still in progress that did not hit a mine can be undone.
+    pub fn can_undo(&self) -> bool {
+        self.undo_stack.last().is_some_and(|step| {
+            self.practice || (self.in_progress() && step.after.lives_lost == step.before.lives_lost)
+        })
+    }
+    /// Takes back the last move, including everything it revealed, and marks the game as
+    /// `undone`. Returns `false` if [`can_undo`](Self::can_undo) does not allow it.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        let Some(step) = self.undo_stack.pop() else {
+            return false;
+        };
+        for &(pos, before, _) in &step.cells {
+            self.grid[pos] = before;
+        }
+        self.set_status(step.before);
+        self.moves.truncate(step.moves);
+        self.undone = true;
+        self.redo_stack.push(step);
+        true
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+    /// Plays the last undone move again. Returns `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(step) = self.redo_stack.pop() else {
+            return false;
+        };
+        for &(pos, _, after) in &step.cells {
+            self.grid[pos] = after;
+        }
+        self.set_status(step.after);
+        self.moves.push(step.m);
+        self.undo_stack.push(step);
+        true
+    }
+    /// A primary click: reveals a hidden cell or chords a revealed number.
+    pub fn primary_click(&mut self, pos: Pos) {
+        let action = if self.grid[pos].state == CellState::Revealed {Action::Chord} else {Action::Reveal};
+        self.play(action, pos);
+    }
+    /// A secondary click: cycles a cell's flags, or flags around a revealed number.
+    pub fn secondary_click(&mut self, pos: Pos) {
+        let action = if self.grid[pos].state == CellState::Revealed {Action::FlagChord} else {Action::Flag};
+        self.play(action, pos);
+    }
+    /// Opens a cell. Hitting a mine costs a life and flags it as exploded; hitting one with no
+    /// lives left ends the game and reveals the board. Opening a zero flood-fills its safe
+    /// neighborhood.
+    pub fn reveal_cell(&mut self, pos: Pos) {
+        if self.game_over || self.game_won || self.grid[pos].state == CellState::Revealed {
+            return;
+        }
+        if self.first_click {
+            if !self.layout_fixed {
+                self.place_mines(pos);
+            }
+            self.first_click = false;
+            self.timer.start();
+        }
+        if self.grid[pos].is_mine() {
+            self.lives_lost += 1;
+            let cell = &mut self.grid[pos];
+            cell.exploded = true;
+            if self.lives_lost < self.config.lives {
+                cell.state = CellState::Flagged;
+                cell.flags = cell.mines;
+                self.check_win_condition();
+                return;
+            }
+            self.game_over = true;
+            self.timer.stop();
+            for cell in self.grid.iter_mut() {
+                if cell.state != CellState::Flagged||cell.is_mine(){
+                    cell.exposed |= cell.state != CellState::Revealed;
+                    cell.state = CellState::Revealed;
+                    cell.flags = 0;
+                }
+            }
+            return;
+        }
+        self.grid[pos].state = CellState::Revealed;
+        if self.grid[pos].adjacent_mines == 0 {
+            self.expand_safe_zone(pos);
+        }
+        self.check_win_condition();
+    }
+    fn expand_safe_zone(&mut self, start: Pos) {
+        // Iterative so huge, sparse boards cannot overflow the stack. Cells are revealed
+        // before being pushed, so each one enters the stack at most once.
+        let mut stack = vec![start];
+        while let Some(pos) = stack.pop() {
+            for n in self.grid.neighbors(pos) {
+                let cell = &mut self.grid[n];
+                if cell.state.is_hidden() && !cell.is_mine() {
+                    cell.state = CellState::Revealed;
+                    if cell.adjacent_mines == 0 {
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+    }
+    /// Adds a flag to a hidden or flagged cell. A cell already holding
+    /// [`mines_per_cell`](GameConfig::mines_per_cell) flags gets a question mark if
+    /// [`question_marks`](GameConfig::question_marks) is set and goes back to hidden otherwise;
+    /// a question mark goes back to hidden.
+    pub fn toggle_flag(&mut self, pos: Pos) {
+        if self.game_over || self.game_won || self.grid[pos].state == CellState::Revealed || self.grid[pos].exploded {
+            return;
+        }
+        if self.first_click {
+            if !self.layout_fixed {
+                self.place_mines(pos);
+            }
+            self.first_click = false;
+            self.timer.start();
+        }
+        let max_flags = self.config.mines_per_cell.max(1);
+        let question_marks = self.config.question_marks;
+        let cell = &mut self.grid[pos];
+        match cell.state {
+            CellState::Hidden => {
+                cell.state = CellState::Flagged;
+                cell.flags = 1;
+            }
+            CellState::Flagged if cell.flags < max_flags => cell.flags += 1,
+            CellState::Flagged => {
+                cell.state = if question_marks {CellState::Questioned} else {CellState::Hidden};
+                cell.flags = 0;
+            }
+            CellState::Questioned => cell.state = CellState::Hidden,
+            CellState::Revealed => {}
+        }
+        self.check_win_condition();
+    }
+    /// Opens every unflagged neighbor of a revealed number whose flag count matches it.
+    pub fn chord(&mut self, pos: Pos) {
+        if self.grid[pos].adjacent_mines==0||self.grid[pos].state!=CellState::Revealed{
+            return;
+        }
+        let near_flagged: usize = self.grid.neighbors(pos).map(|n| self.grid[n].flags as usize).sum();
+        if near_flagged == self.grid[pos].adjacent_mines as usize {
+            for n in self.grid.neighbors(pos) {
+                if self.grid[n].state != CellState::Flagged {
+                    self.reveal_cell(n);
+                }
+            }
+        }
+    }
+    /// Fully flags every hidden neighbor of a revealed number if only full cells can remain:
+    /// the flags around it plus a full load of mines on each hidden neighbor match the number.
+    pub fn flag_neighbors(&mut self, pos: Pos) {
+        if self.grid[pos].adjacent_mines==0||self.grid[pos].state!=CellState::Revealed{
+            return;
+        }
+        let max_flags = self.config.mines_per_cell.max(1);
+        let near_capacity: usize = self.grid.neighbors(pos).map(|n| match self.grid[n].state {
+            CellState::Hidden | CellState::Questioned => max_flags as usize,
+            CellState::Flagged => self.grid[n].flags as usize,
+            CellState::Revealed => 0,
+        }).sum();
+        if near_capacity == self.grid[pos].adjacent_mines as usize {
+            for n in self.grid.neighbors(pos) {
+                if self.grid[n].state.is_hidden() {
+                    self.grid[n].state = CellState::Flagged;
+                    self.grid[n].flags = max_flags;
+                }
+            }
+            self.check_win_condition();
+        }
+    }
+    /// Lives not lost yet.
+    pub fn lives_left(&self) -> u32 {
+        self.config.lives.saturating_sub(self.lives_lost)
+    }
+    /// Mines minus flags placed; negative if the player has over-flagged.
+    pub fn mines_remaining(&self) -> isize {
+        let flags: usize = self.grid.iter().map(|cell| cell.flags as usize).sum();
+        self.config.mine_count as isize - flags as isize
+    }
+    /// Asks the solver for a move (see [`solver::hint`]) and counts it in `hints_used`.
+    pub fn hint(&mut self) -> Option<Hint> {
+        let hint = solver::hint(self);
+        if hint.is_some() {
+            self.hints_used += 1;
+        }
+        hint
+    }
+    /// Sets `game_won` once every safe cell is revealed or every cell carries exactly as many
+    /// flags as mines, flagging all mines in that case. Mines that exploded were not found by
+    /// the player, so after a hit only revealing every safe cell wins.
+    pub fn check_win_condition(&mut self) {
+        let all_non_mines_revealed = !self.first_click&&self.grid.iter().all(|cell|
+            if cell.is_mine() {
+                cell.state != CellState::Revealed
+            } else {
+                cell.state == CellState::Revealed
+            }
+        );
+
+        let all_mines_flagged = self.grid.iter().all(|cell|
+            cell.mines == cell.flags && !cell.exploded
+        );
+
+        if all_non_mines_revealed || all_mines_flagged {
+            self.game_won = true;
+            self.timer.stop();
+            for cell in self.grid.iter_mut() {
+                if cell.is_mine() {
+                    cell.state = CellState::Flagged;
+                    cell.flags = cell.mines;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(width: usize, height: usize, mines: &[Pos], lives: u32) -> Minesweeper {
+        let config = GameConfig {width, height, lives, ..GameConfig::default()};
+        Minesweeper::with_layout(config, mines)
+    }
+
+    #[test]
+    fn exploded_mines_do_not_win_by_flags() {
+        let mut game = game(6, 6, &[Pos::new(0, 0), Pos::new(5, 5)], 3);
+        game.secondary_click(Pos::new(0, 0));
+        game.primary_click(Pos::new(5, 5));
+        assert!(!game.game_won && !game.game_over);
+        assert_eq!(game.lives_left(), 2);
+        game.primary_click(Pos::new(3, 3));
+        assert!(game.game_won);
+    }
+
+    #[test]
+    fn crowded_boards_get_the_mines_that_fit() {
+        let config = GameConfig {width: 3, height: 3, mine_count: 10, ..GameConfig::default()};
+        let mut game = Minesweeper::with_seed(config, 1);
+        game.primary_click(Pos::new(1, 1));
+        assert_eq!(game.config.mine_count, 0);
+        assert!(game.game_won);
+        let config = GameConfig {width: 4, height: 3, mine_count: 20, mines_per_cell: 2, ..GameConfig::default()};
+        let mut game = Minesweeper::with_seed(config, 1);
+        game.primary_click(Pos::new(0, 1));
+        assert_eq!((game.config.mine_count, game.mine_positions().len()), (12, 12));
+    }
+
+    #[test]
+    fn undo_and_redo_skip_moves_that_changed_nothing() {
+        let mut game = game(6, 6, &[Pos::new(0, 0), Pos::new(3, 1), Pos::new(2, 2)], 1);
+        game.primary_click(Pos::new(1, 0));
+        game.primary_click(Pos::new(1, 0));
+        assert_eq!((game.moves.len(), game.undo_stack.len()), (2, 1));
+        game.secondary_click(Pos::new(0, 0));
+        let flagged = game.grid.clone();
+        game.primary_click(Pos::new(1, 0));
+        assert_ne!(game.grid, flagged);
+        assert!(game.undo());
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.grid, flagged);
+        assert!(game.redo() && !game.redo());
+        assert_eq!(game.moves.len(), 4);
+        assert!(game.undo() && game.undo() && game.undo());
+        assert!(game.moves.is_empty() && game.first_click && !game.timer.is_running());
+        assert!(game.grid.iter().all(|cell| cell.state == CellState::Hidden));
+        assert!(!game.can_undo() && game.undone);
+    }
+
+    #[test]
+    fn only_practice_undoes_mine_hits() {
+        let mut game = game(6, 6, &[Pos::new(0, 0)], 2);
+        game.primary_click(Pos::new(0, 0));
+        assert_eq!(game.lives_lost, 1);
+        assert!(!game.can_undo());
+        game.practice = true;
+        assert!(game.undo());
+        assert_eq!(game.lives_lost, 0);
+        assert!(game.grid.iter().all(|cell| cell.state.is_hidden() && !cell.exploded));
+    }
+
+    #[test]
+    fn question_marks_are_effective() {
+        let mut game = game(6, 6, &[Pos::new(0, 0), Pos::new(5, 5)], 1);
+        for _ in 0..3 {
+            game.secondary_click(Pos::new(0, 0));
+        }
+        assert_eq!(game.grid[Pos::new(0, 0)].state, CellState::Hidden);
+        game.primary_click(Pos::new(1, 0));
+        game.primary_click(Pos::new(1, 0));
+        let effective: Vec<bool> = game.moves.iter().map(|m| m.effective).collect();
+        assert_eq!(effective, [true, true, true, true, false]);
+    }
+
+    #[test]
+    fn failed_no_guess_search_falls_back_to_the_plain_layout() {
+        let config = GameConfig {width: 9, height: 9, mine_count: 60, ..GameConfig::default()};
+        let mut plain = Minesweeper::with_seed(config.clone(), 5);
+        plain.place_mines(Pos::new(4, 4));
+        let mut game = Minesweeper::with_seed(GameConfig {no_guess: true, ..config}, 5);
+        assert!(!game.place_mines(Pos::new(4, 4)));
+        assert!(game.no_guess_failed);
+        assert_eq!(game.mine_positions(), plain.mine_positions());
+    }
+
+    #[test]
+    fn cells_serialize_compactly_and_read_old_saves() {
+        assert_eq!(serde_json::to_string(&Cell::default()).unwrap(), "{}");
+        let cell = Cell {state: CellState::Revealed, adjacent_mines: 2, ..Cell::default()};
+        assert_eq!(serde_json::to_string(&cell).unwrap(), r#"{"state":"Revealed","adjacent_mines":2}"#);
+        let old = r#"{"is_mine":false,"state":"Revealed","adjacent_mines":2}"#;
+        assert_eq!(serde_json::from_str::<Cell>(old).unwrap(), cell);
+        let mut game = game(6, 6, &[Pos::new(0, 0), Pos::new(5, 5)], 2);
+        game.primary_click(Pos::new(0, 0));
+        game.primary_click(Pos::new(3, 3));
+        assert_eq!(Minesweeper::from_json(&game.to_json()).unwrap().grid, game.grid);
+    }
+
+    #[test]
+    fn seeds_reproduce_layouts() {
+        let config = GameConfig {width: 9, height: 9, mine_count: 10, ..GameConfig::default()};
+        let layout = |seed| {
+            let mut game = Minesweeper::with_seed(config.clone(), seed);
+            game.primary_click(Pos::new(4, 4));
+            game.mine_positions()
+        };
+        assert_eq!(layout(7), layout(7));
+        assert_ne!(layout(7), layout(8));
+        // Pinned so that a `rand` or `rand_chacha` upgrade changing the layout of a seed is noticed.
+        let expected = [(0, 1), (5, 1), (2, 2), (5, 2), (2, 3), (7, 5), (6, 6), (7, 6), (8, 7), (6, 8)];
+        assert_eq!(layout(42), expected.map(|(x, y)| Pos::new(x, y)));
+    }
+}
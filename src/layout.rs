@@ -0,0 +1,257 @@
+//! Fixed mine layouts: generated for puzzle sets and test fixtures, or drawn by hand and
+//! imported to teach specific patterns and reproduce bug reports.
+//!
+//! The text format draws the board one line per row, after `#` comment lines:
+//!
+//! ```text
+//! # 9x9, 10 mines, seed 42, first click 4,4
+//! .........
+//! *....*...
+//! ..*..*...
+//! ..*......
+//! .........
+//! .......*.
+//! ......**.
+//! ........*
+//! ......*..
+//! ```
+//!
+//! `.` is a cell without mines, `*` a cell with one mine and `2`–`9` a cell holding that many
+//! mines (see [`GameConfig::mines_per_cell`]). Holes of shaped boards are spaces, and
+//! trailing spaces are trimmed. The text only shows where mines are; the JSON form
+//! (`{"config": {...}, "seed": 42, "first_click": [4, 4], "mines": [[0, 1], ...]}`, with `[x, y]`
+//! positions) also keeps the topology, neighborhood and other settings.
+//!
+//! Both forms can be read back with [`Layout::load`]. Hand-written text may also use `x` for a
+//! mine and `0` for an empty cell, and empty margins are cropped.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mask::Mask;
+use crate::{GameConfig, Minesweeper, Pos};
+
+/// Where the mines of one board are, and how they were generated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layout {
+    pub config: GameConfig,
+    pub seed: u64,
+    /// The cell whose neighborhood was kept free of mines, if any.
+    pub first_click: Option<Pos>,
+    /// Mine positions in row order, repeated for cells holding several.
+    pub mines: Vec<Pos>,
+}
+impl Layout {
+    /// Lays out mines with [`Minesweeper::place_mines`], exactly as a game with this config and
+    /// seed would on a first click at `first_click`. Without a first click every cell may get
+    /// a mine (see [`Minesweeper::place_mines_anywhere`]). Fails if `config.no_guess` is set and
+    /// no board solvable without guessing is found.
+    pub fn generate(config: GameConfig, seed: u64, first_click: Option<Pos>) -> Result<Self, String> {
+        if let Some(pos) = first_click
+            && (pos.x >= config.width || pos.y >= config.height || config.mask.as_ref().is_some_and(|mask| !mask.contains(pos)))
+        {
+            return Err(format!("first click {},{} is not on the board", pos.x, pos.y));
+        }
+        let max_mines = match first_click {
+            Some(_) => config.max_mines(),
+            None => config.cell_count() * config.mines_per_cell.max(1) as usize,
+        };
+        if config.mine_count > max_mines {
+            return Err(format!("{} mines do not fit, at most {max_mines} can be placed", config.mine_count));
+        }
+        let mut game = Minesweeper::with_seed(config, seed);
+        match first_click {
+            Some(pos) if !game.place_mines(pos) => return Err(format!("found no board solvable without guessing for seed {seed}")),
+            Some(_) => {}
+            None => game.place_mines_anywhere(),
+        }
+        Ok(Self {mines: game.mine_positions(), config: game.config, seed, first_click})
+    }
+    /// Draws the layout in the text format described in the [module docs](self).
+    /// Cells holding more than 9 mines cannot be drawn and show as `9`.
+    pub fn to_text(&self) -> String {
+        let config = &self.config;
+        let mut text = format!("# {}x{}, {} mines, seed {}", config.width, config.height, config.mine_count, self.seed);
+        if let Some(pos) = self.first_click {
+            text += &format!(", first click {},{}", pos.x, pos.y);
+        }
+        let mut counts = vec![0; config.width * config.height];
+        for pos in &self.mines {
+            counts[pos.y * config.width + pos.x] += 1;
+        }
+        for y in 0..config.height {
+            let row: String = (0..config.width).map(|x| match counts[y * config.width + x] {
+                _ if config.mask.as_ref().is_some_and(|mask| !mask.contains(Pos::new(x, y))) => ' ',
+                0 => '.',
+                1 => '*',
+                n => char::from_digit(n.min(9), 10).expect("single digit"),
+            }).collect();
+            text.push('\n');
+            text += row.trim_end();
+        }
+        text.push('\n');
+        text
+    }
+    /// Serializes the layout as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("layouts are always serializable")
+    }
+    /// Reads the text format described in the [module docs](self). The board is square,
+    /// rectangular or, if it has holes, shaped by a mask called `name`; other settings are
+    /// defaults and the seed is 0.
+    pub fn from_text(name: &str, text: &str) -> Result<Self, String> {
+        let mut rows = Vec::new();
+        for (line_number, line) in text.lines().enumerate().filter(|(_, line)| !line.starts_with('#')) {
+            let row = line.chars().enumerate().map(|(column, c)| match c {
+                ' ' => Ok(None),
+                '.' => Ok(Some(0)),
+                '*' | 'x' | 'X' => Ok(Some(1)),
+                c => c.to_digit(10).map(|n| Some(n as u8))
+                    .ok_or_else(|| format!("unexpected '{c}' on line {}, column {}", line_number + 1, column + 1)),
+            }).collect::<Result<Vec<_>, _>>()?;
+            rows.push(row);
+        }
+        let cell = |x: usize, y: usize| rows[y].get(x).copied().flatten();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let columns: Vec<usize> = (0..width).filter(|&x| (0..rows.len()).any(|y| cell(x, y).is_some())).collect();
+        let lines: Vec<usize> = (0..rows.len()).filter(|&y| (0..width).any(|x| cell(x, y).is_some())).collect();
+        let (Some(&left), Some(&right), Some(&top), Some(&bottom)) = (columns.first(), columns.last(), lines.first(), lines.last()) else {
+            return Err("board has no cells".to_string());
+        };
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        let cells: Vec<Option<u8>> = (top..=bottom).flat_map(|y| (left..=right).map(move |x| (x, y))).map(|(x, y)| cell(x, y)).collect();
+        let mask = if cells.contains(&None) {
+            let art: Vec<String> = cells.chunks(width).map(|row| row.iter().map(|c| if c.is_some() {'#'} else {'.'}).collect()).collect();
+            Some(Mask::parse(name, &art.join("\n"))?)
+        } else {
+            None
+        };
+        let mines: Vec<Pos> = cells.iter().enumerate()
+            .flat_map(|(i, c)| std::iter::repeat_n(Pos::new(i % width, i / width), c.unwrap_or(0) as usize))
+            .collect();
+        let mines_per_cell = cells.iter().flatten().copied().max().unwrap_or(0).max(1);
+        let config = GameConfig {width, height, mine_count: mines.len(), mines_per_cell, mask, ..GameConfig::default()};
+        Ok(Self {config, seed: 0, first_click: None, mines})
+    }
+    /// Parses a layout written by [`to_json`](Self::to_json), rejecting empty boards,
+    /// `mines_per_cell` outside 1 to [`GameConfig::MAX_MINES_PER_CELL`], mines outside the board
+    /// and cells with more than `mines_per_cell` mines.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let layout: Self = serde_json::from_str(json)?;
+        check_mines(&layout.config, &layout.mines).map_err(serde::de::Error::custom)?;
+        if !layout.first_click.iter().all(|&pos| inside(&layout.config, pos)) {
+            return Err(serde::de::Error::custom("first click is outside the board"));
+        }
+        Ok(layout)
+    }
+    /// Loads a layout file: JSON if it starts with `{`, the text format otherwise. The file
+    /// stem names the mask of shaped text boards.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        if text.trim_start().starts_with('{') {
+            return Self::from_json(&text).map_err(|e| invalid(e.to_string()));
+        }
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::from_text(&name, &text).map_err(invalid)
+    }
+    /// A game with exactly these mines: the first move does not generate a layout and may hit
+    /// a mine (see [`Minesweeper::with_layout`]).
+    pub fn game(&self) -> Minesweeper {
+        let mut game = Minesweeper::with_layout(self.config.clone(), &self.mines);
+        game.seed = self.seed;
+        game
+    }
+}
+
+/// Whether `pos` is a cell of a board of `config`.
+pub(crate) fn inside(config: &GameConfig, pos: Pos) -> bool {
+    pos.x < config.width && pos.y < config.height && config.mask.as_ref().is_none_or(|mask| mask.contains(pos))
+}
+
+/// Checks that `mines` can be laid on a board of `config` with [`Minesweeper::with_layout`]:
+/// the board has cells, `mines_per_cell` is from 1 to [`GameConfig::MAX_MINES_PER_CELL`], every
+/// mine is on the board, no cell holds more than `mines_per_cell` mines and no number exceeds 255.
+pub(crate) fn check_mines(config: &GameConfig, mines: &[Pos]) -> Result<(), String> {
+    if config.width == 0 || config.height == 0 {
+        return Err("board has no cells".to_string());
+    }
+    if !(1..=GameConfig::MAX_MINES_PER_CELL).contains(&config.mines_per_cell) {
+        return Err(format!("mines_per_cell must be from 1 to {}", GameConfig::MAX_MINES_PER_CELL));
+    }
+    if !mines.iter().all(|&pos| inside(config, pos)) {
+        return Err("mines outside the board".to_string());
+    }
+    let mut counts = config.board(0);
+    for &pos in mines {
+        counts[pos] += 1;
+    }
+    if counts.iter().any(|&n| n > config.mines_per_cell as usize) {
+        return Err("a cell holds more mines than mines_per_cell allows".to_string());
+    }
+    if counts.positions().any(|pos| counts.neighbors(pos).map(|n| counts[n]).sum::<usize>() > u8::MAX as usize) {
+        return Err("a cell has more mines around it than numbers can show".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trips() {
+        let config = GameConfig {width: 9, height: 7, mine_count: 20, mines_per_cell: 3, ..GameConfig::default()};
+        let layout = Layout::generate(config, 42, Some(Pos::new(4, 3))).unwrap();
+        let read = Layout::from_text("", &layout.to_text()).unwrap();
+        assert_eq!(read.mines, layout.mines);
+        assert_eq!((read.config.width, read.config.height, read.config.mine_count), (9, 7, 20));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let layout = Layout::generate(GameConfig::default(), 7, None).unwrap();
+        assert_eq!(Layout::from_json(&layout.to_json()).unwrap(), layout);
+    }
+
+    #[test]
+    fn parses_hand_written_boards() {
+        let layout = Layout::from_text("pattern", "# a comment\n.x0\n*.2\n").unwrap();
+        assert_eq!((layout.config.width, layout.config.height, layout.config.mines_per_cell), (3, 2, 2));
+        assert_eq!(layout.mines, [Pos::new(1, 0), Pos::new(0, 1), Pos::new(2, 1), Pos::new(2, 1)]);
+        assert_eq!(layout.config.mask, None);
+        let error = Layout::from_text("", "..\n.?\n").unwrap_err();
+        assert_eq!(error, "unexpected '?' on line 2, column 2");
+        assert!(Layout::from_text("", "# only comments\n  \n").is_err());
+    }
+
+    #[test]
+    fn crops_margins_and_masks_holes() {
+        let layout = Layout::from_text("ring", "\n   .*.\n   . .\n   ...\n\n").unwrap();
+        let config = &layout.config;
+        assert_eq!((config.width, config.height), (3, 3));
+        assert_eq!(layout.mines, [Pos::new(1, 0)]);
+        let mask = config.mask.as_ref().unwrap();
+        assert_eq!((mask.name(), mask.cell_count()), ("ring", 8));
+        assert!(!mask.contains(Pos::new(1, 1)));
+        assert_eq!(layout.to_text(), "# 3x3, 1 mines, seed 0\n.*.\n. .\n...\n");
+        let game = layout.game();
+        assert!(game.layout_fixed && !game.grid.contains(Pos::new(1, 1)));
+        assert_eq!(game.grid[Pos::new(0, 1)].adjacent_mines, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        let json = |config: &str, mines: &str| format!(r#"{{"config": {{{config}}}, "seed": 0, "first_click": null, "mines": {mines}}}"#);
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2"#, "[[1, 1]]")).is_ok());
+        assert!(Layout::from_json(&json(r#""width": 0, "height": 2"#, "[]")).is_err());
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2"#, "[[2, 0]]")).is_err());
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2"#, "[[1, 1], [1, 1]]")).is_err());
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2, "mines_per_cell": 2"#, "[[1, 1], [1, 1]]")).is_ok());
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2, "mines_per_cell": 0"#, "[]")).is_err());
+        assert!(Layout::from_json(&json(r#""width": 2, "height": 2, "mines_per_cell": 10"#, "[]")).is_err());
+    }
+}
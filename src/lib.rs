@@ -0,0 +1,23 @@
+//! Minesweeper rules engine.
+//!
+//! The board model ([`Minesweeper`], [`Cell`], [`CellState`], [`GameConfig`], stored in a
+//! [`Board`] indexed by [`Pos`]) has no GUI dependencies. The egui frontend is the `minesweeper`
+//! binary, built with the `gui` feature (enabled by default), and a terminal frontend is the
+//! `minesweeper-tui` binary, built with the `tui` feature (also default); tools that only need
+//! the rules can depend on this crate with `default-features = false`. The `minesweeper-cli`
+//! binary needs no feature and exports generated boards as [`layout`]s.
+//!
+//! [`solver`] computes what the player can logically deduce from the visible board, and
+//! [`stats`] keeps win/loss records per board configuration. [`replay`] stores recorded games and
+//! [`metrics`] rates them (3BV, 3BV/s, IOE). [`mask`] describes non-rectangular board shapes.
+mod board;
+mod game;
+pub mod layout;
+pub mod mask;
+pub mod metrics;
+pub mod replay;
+pub mod solver;
+pub mod stats;
+
+pub use board::{Board, Neighborhood, Neighbors, Pos, Topology};
+pub use game::{Action, Cell, CellState, GameConfig, Minesweeper, Move, Timer};
@@ -1,186 +1,392 @@
 use eframe::egui;
-use rand::seq::index::sample;
+use minesweeper::solver::{self, Hint};
+use minesweeper::replay::Replay;
+use minesweeper::layout::Layout;
+use minesweeper::mask::Mask;
+use minesweeper::metrics::Metrics;
+use minesweeper::stats::Statistics;
+use minesweeper::{Action, Board, CellState, GameConfig, Minesweeper, Neighborhood, Pos, Topology};
 
-#[derive(Clone, Copy, PartialEq)]
-enum CellState {Hidden, Revealed, Flagged}
-#[derive(Clone, Copy)]
-struct Cell {is_mine: bool, state: CellState, adjacent_mines: u8}
-impl Default for Cell {
-    fn default() -> Self {
-        Self {is_mine: false, state: CellState::Hidden, adjacent_mines: 0}
-    }
+const STATISTICS_KEY: &str = "statistics";
+const GAME_KEY: &str = "game";
+/// Largest width/height offered in the settings.
+const MAX_BOARD_SIZE: usize = 1000;
+/// Cells never shrink below this; larger boards scroll instead.
+const MIN_CELL_SIZE: f32 = 16.0;
+
+/// Where cells are drawn on screen and which cell a point falls in.
+///
+/// `cell_size` is the width of a cell; hex cells are pointy-top, so their height is
+/// `2 / √3` times that and rows overlap by a quarter. On wrapping boards, `pan` is the board
+/// cell drawn in the top-left corner; other boards always use `(0, 0)`.
+struct BoardGeometry {
+    origin: egui::Pos2,
+    cell_size: f32,
+    topology: Topology,
+    width: usize,
+    height: usize,
+    pan: Pos,
 }
-#[derive(Clone, Copy)]
-struct GameConfig {width: usize, height: usize, mine_count: usize}
-impl Default for GameConfig {
-    fn default() -> Self {
-        Self {width: 10, height: 10, mine_count: 15}
+impl BoardGeometry {
+    /// Largest cell size at which a `config` board fits in `available`.
+    fn fit(config: &GameConfig, available: egui::Vec2) -> f32 {
+        match config.topology {
+            Topology::Square => (available.x / config.width as f32).min(available.y / config.height as f32),
+            Topology::Hex => (available.x / (config.width as f32 + 0.5))
+                .min(available.y * 3f32.sqrt() / (1.5 * config.height as f32 + 0.5)),
+        }
     }
-}
-struct Minesweeper {grid: Vec<Vec<Cell>>, config: GameConfig, game_over: bool, game_won: bool, first_click: bool}
-impl Minesweeper {
-    fn new(config: GameConfig) -> Self {
-        Self {
-            grid: vec![vec![Cell::default(); config.width]; config.height],
-            config,
-            game_over: false,
-            game_won: false,
-            first_click: true,
+    /// Size of the whole board on screen.
+    fn board_size(config: &GameConfig, cell_size: f32) -> egui::Vec2 {
+        match config.topology {
+            Topology::Square => egui::Vec2::new(config.width as f32, config.height as f32) * cell_size,
+            Topology::Hex => egui::Vec2::new(
+                (config.width as f32 + 0.5) * cell_size,
+                (1.5 * config.height as f32 + 0.5) * cell_size / 3f32.sqrt()
+            ),
         }
     }
-    fn reset(&mut self) {
-        self.grid = vec![vec![Cell::default(); self.config.width]; self.config.height];
-        self.game_over = false;
-        self.game_won = false;
-        self.first_click = true;
+    /// Distance from a hex center to its corners.
+    fn radius(&self) -> f32 {
+        self.cell_size / 3f32.sqrt()
     }
-    fn place_mines(&mut self, safe_x: usize, safe_y: usize) {
-        let mut rng = rand::rng();
-        let mut space:Vec<Vec<usize>>=vec![vec![0;2];0];
-        for y in 0..self.config.height {
-            for x in 0..self.config.width {
-                if y.abs_diff(safe_y)>1||x.abs_diff(safe_x)>1{
-                    space.append(&mut vec![vec![x,y]; 1]);
-                }
-            }
-        }
-        let mine_places=sample(&mut rng,space.len(),self.config.mine_count);
-        for mi in mine_places{
-            self.grid[space[mi][1]][space[mi][0]].is_mine=true;
-        }
-        for y in 0..self.config.height {
-            for x in 0..self.config.width {
-                if !self.grid[y][x].is_mine {
-                    self.grid[y][x].adjacent_mines = self.count_adjacent_mines(x, y);
-                }
-            }
+    fn row_height(&self) -> f32 {
+        match self.topology {
+            Topology::Square => self.cell_size,
+            Topology::Hex => 1.5 * self.radius(),
         }
     }
-    fn count_adjacent_mines(&self, x: usize, y: usize) -> u8 {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as isize + dx;
-                let ny = y as isize + dy;
-                if nx >= 0 && ny >= 0 && nx < self.config.width as isize && ny < self.config.height as isize {
-                    if self.grid[ny as usize][nx as usize].is_mine {
-                        count += 1;
-                    }
-                }
-            }
-        }
-        count
+    /// Board cell shown at screen cell `screen`.
+    fn to_board(&self, screen: Pos) -> Pos {
+        Pos::new((screen.x + self.pan.x) % self.width, (screen.y + self.pan.y) % self.height)
     }
-    fn reveal_cell(&mut self, x: usize, y: usize) {
-        if self.game_over || self.game_won || self.grid[y][x].state == CellState::Revealed {
-            return;
-        }
-        if self.first_click {
-            self.place_mines(x, y);
-            self.first_click = false;
+    fn center(&self, pos: Pos) -> egui::Pos2 {
+        let pos = Pos::new((pos.x + self.width - self.pan.x) % self.width, (pos.y + self.height - self.pan.y) % self.height);
+        match self.topology {
+            Topology::Square => self.origin + egui::Vec2::new(pos.x as f32 + 0.5, pos.y as f32 + 0.5) * self.cell_size,
+            Topology::Hex => self.origin + egui::Vec2::new(
+                (pos.x as f32 + 0.5 + 0.5 * (pos.y % 2) as f32) * self.cell_size,
+                self.radius() + pos.y as f32 * self.row_height()
+            ),
         }
-        if self.grid[y][x].is_mine {
-            self.game_over = true;
-            for row in &mut self.grid {
-                for cell in row {
-                    if !(cell.state == CellState::Flagged&&!cell.is_mine){
-                        cell.state = CellState::Revealed;
-                    }
-                }
+    }
+    /// Corners of the cell, moved `inset` pixels towards its center.
+    fn outline(&self, pos: Pos, inset: f32) -> Vec<egui::Pos2> {
+        let center = self.center(pos);
+        match self.topology {
+            Topology::Square => {
+                let rect = egui::Rect::from_center_size(center, egui::Vec2::splat(self.cell_size)).shrink(inset);
+                vec![rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()]
             }
-            return;
+            Topology::Hex => (0..6).map(|i| {
+                let angle = (60.0 * i as f32 - 90.0).to_radians();
+                center + egui::Vec2::angled(angle) * (self.radius() - inset)
+            }).collect(),
         }
-        self.grid[y][x].state = CellState::Revealed;
-        if self.grid[y][x].adjacent_mines == 0 {
-            self.expand_safe_zone(x, y);
-        }
-        self.check_win_condition();
     }
-    fn expand_safe_zone(&mut self, x: usize, y: usize) {
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as isize + dx;
-                let ny = y as isize + dy;
-                if nx >= 0 && ny >= 0 && nx < self.config.width as isize && ny < self.config.height as isize {
-                    let nx = nx as usize;
-                    let ny = ny as usize;
-                    if self.grid[ny][nx].state == CellState::Hidden && !self.grid[ny][nx].is_mine {
-                        self.grid[ny][nx].state = CellState::Revealed;
-                        if self.grid[ny][nx].adjacent_mines == 0 {
-                            self.expand_safe_zone(nx, ny);
+    /// The cell under `point`; for hexes, the one with the nearest center.
+    fn cell_at(&self, point: egui::Pos2) -> Option<Pos> {
+        let offset = point - self.origin;
+        match self.topology {
+            Topology::Square => {
+                let (x, y) = ((offset.x / self.cell_size).floor(), (offset.y / self.cell_size).floor());
+                (x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height)
+                    .then(|| self.to_board(Pos::new(x as usize, y as usize)))
+            }
+            Topology::Hex => {
+                let row = ((offset.y - self.radius()) / self.row_height()).round() as isize;
+                let mut best: Option<(Pos, f32)> = None;
+                for y in (row - 1..=row + 1).filter(|&y| y >= 0 && (y as usize) < self.height) {
+                    let column = (offset.x / self.cell_size - 0.5 - 0.5 * (y % 2) as f32).round() as isize;
+                    for x in (column - 1..=column + 1).filter(|&x| x >= 0 && (x as usize) < self.width) {
+                        let pos = self.to_board(Pos::new(x as usize, y as usize));
+                        let distance = self.center(pos).distance(point);
+                        if distance <= self.radius() && best.is_none_or(|(_, d)| distance < d) {
+                            best = Some((pos, distance));
                         }
                     }
                 }
+                best.map(|(pos, _)| pos)
             }
         }
     }
-    fn toggle_flag(&mut self, x: usize, y: usize) {
-        if self.game_over || self.game_won || self.grid[y][x].state == CellState::Revealed {
-            return;
-        }
-        if self.first_click {
-            self.place_mines(x, y);
-            self.first_click = false;
+    /// Board cells that may intersect `rect`.
+    fn cells_in(&self, rect: egui::Rect) -> impl Iterator<Item = Pos> + '_ {
+        let columns = (rect.min.x - self.origin.x) / self.cell_size - 1.0..(rect.max.x - self.origin.x) / self.cell_size + 1.0;
+        let rows = (rect.min.y - self.origin.y) / self.row_height() - 1.0..(rect.max.y - self.origin.y) / self.row_height() + 1.0;
+        let clamp = |range: std::ops::Range<f32>, len: usize| {
+            (range.start.max(0.0) as usize).min(len)..(range.end.max(0.0).ceil() as usize).min(len)
+        };
+        let columns = clamp(columns, self.width);
+        clamp(rows, self.height).flat_map(move |y| columns.clone().map(move |x| self.to_board(Pos::new(x, y))))
+    }
+    /// Screen lines along the board's real top and left edges, if panning moved them inside.
+    fn seams(&self, board: egui::Rect) -> Vec<[egui::Pos2; 2]> {
+        let mut seams = Vec::new();
+        if self.pan.x > 0 {
+            let x = self.origin.x + (self.width - self.pan.x) as f32 * self.cell_size;
+            seams.push([egui::pos2(x, board.min.y), egui::pos2(x, board.max.y)]);
         }
-        match self.grid[y][x].state {
-            CellState::Hidden => self.grid[y][x].state = CellState::Flagged,
-            CellState::Flagged => self.grid[y][x].state = CellState::Hidden,
-            _ => {}
+        if self.pan.y > 0 {
+            let y = self.origin.y + (self.height - self.pan.y) as f32 * self.row_height()
+                + if self.topology == Topology::Hex {0.25 * self.radius()} else {0.0};
+            seams.push([egui::pos2(board.min.x, y), egui::pos2(board.max.x, y)]);
         }
-        self.check_win_condition();
+        seams
     }
-    fn check_win_condition(&mut self) {
-        let all_non_mines_revealed = !self.first_click&&self.grid.iter().flatten().all(|cell|
-            if cell.is_mine {
-                cell.state != CellState::Revealed
-            } else {
-                cell.state == CellState::Revealed
-            }
-        );
+}
 
-        let all_mines_flagged = self.grid.iter().flatten().all(|cell|
-            cell.is_mine == (cell.state == CellState::Flagged)
-        );
+/// Label of a neighborhood in the settings window and statistics.
+fn neighborhood_name(neighborhood: &Neighborhood) -> &'static str {
+    match neighborhood {
+        Neighborhood::Adjacent => "Adjacent",
+        Neighborhood::VonNeumann => "Cross",
+        Neighborhood::Radius2 => "Radius 2",
+        Neighborhood::Knight => "Knight's move",
+        Neighborhood::Custom(_) => "Custom",
+    }
+}
 
-        if all_non_mines_revealed || all_mines_flagged {
-            self.game_won = true;
-            for row in &mut self.grid {
-                for cell in row {
-                    if cell.is_mine {
-                        cell.state = CellState::Flagged;
-                    }
-                }
-            }
+/// Mine probabilities for the heat-map overlay, recomputed only when the board changes.
+struct HeatMap {
+    seed: u64,
+    /// State and flag count of every cell when the probabilities were computed.
+    cells: Vec<(CellState, u8)>,
+    probabilities: Board<Option<f64>>,
+    safest: Option<Pos>,
+}
+
+/// Step-by-step playback of a [`Replay`] on its own board.
+struct ReplayViewer {
+    replay: Replay,
+    board: Minesweeper,
+    /// Number of moves applied to `board`.
+    step: usize,
+    playing: bool,
+    /// Playback position in game time.
+    clock: std::time::Duration,
+}
+impl ReplayViewer {
+    fn new(replay: Replay) -> Self {
+        Self {board: replay.position(0), replay, step: 0, playing: true, clock: std::time::Duration::ZERO}
+    }
+    fn seek(&mut self, step: usize) {
+        self.step = step.min(self.replay.moves.len());
+        self.board = self.replay.position(self.step);
+        self.clock = self.step.checked_sub(1).map_or(std::time::Duration::ZERO, |last| self.replay.moves[last].time);
+    }
+    /// Plays every move whose time has been reached after `dt` more seconds of playback.
+    fn advance(&mut self, dt: f32) {
+        self.clock += std::time::Duration::from_secs_f32(dt);
+        while let Some(m) = self.replay.moves.get(self.step) && m.time <= self.clock {
+            self.board.play(m.action, m.pos());
+            self.step += 1;
+        }
+        if self.step == self.replay.moves.len() {
+            self.playing = false;
         }
     }
 }
+
 struct MinesweeperApp {
     game: Minesweeper,
     show_settings: bool,
     temp_config: GameConfig,
+    seed_input: String,
     presets: Vec<(String, GameConfig)>,
+    show_heat_map: bool,
+    heat_map: Option<HeatMap>,
+    hint: Option<Hint>,
+    statistics: Statistics,
+    /// Whether the current game has already been added to `statistics`.
+    recorded: bool,
+    /// Metrics of the current game once it is over, computed on first display.
+    metrics: Option<Metrics>,
+    show_statistics: bool,
+    show_game_file: bool,
+    game_path: String,
+    game_file_status: String,
+    replay_path: String,
+    replay: Option<ReplayViewer>,
+    /// Accumulated drag on wrapping boards, in pixels; see [`BoardGeometry::pan`].
+    pan: egui::Vec2,
+    mask_path: String,
+    mask_status: String,
+    /// Fixed mine layout to open from the settings window (see [`Layout::load`]).
+    board_path: String,
+    board_status: String,
+    /// Keyboard cursor; shown once a movement key is pressed.
+    cursor: Option<Pos>,
+    /// The cursor moved and the board should scroll to it.
+    scroll_to_cursor: bool,
+    /// Start new games in practice mode (see [`Minesweeper::practice`]).
+    practice: bool,
+    temp_practice: bool,
 }
 impl Default for MinesweeperApp {
     fn default() -> Self {
-        let presets = vec![
-            ("Beginner (9×9, 10 mines)".to_string(), GameConfig { width: 9, height: 9, mine_count: 10 }),
-            ("Intermediate (16×16, 40 mines)".to_string(), GameConfig { width: 16, height: 16, mine_count: 40 }),
-            ("Expert (30×16, 99 mines)".to_string(), GameConfig { width: 30, height: 16, mine_count: 99 })
-        ];
+        let presets = GameConfig::presets().into_iter().map(|(name, config)| (name.to_string(), config)).collect();
         Self {
             game: Minesweeper::new(GameConfig::default()),
             show_settings: false,
             temp_config: GameConfig::default(),
+            seed_input: String::new(),
             presets,
+            show_heat_map: false,
+            heat_map: None,
+            hint: None,
+            statistics: Statistics::default(),
+            recorded: false,
+            metrics: None,
+            show_statistics: false,
+            show_game_file: false,
+            game_path: "minesweeper-save.json".to_string(),
+            game_file_status: String::new(),
+            replay_path: "minesweeper-replay.json".to_string(),
+            replay: None,
+            pan: egui::Vec2::ZERO,
+            mask_path: String::new(),
+            mask_status: String::new(),
+            board_path: String::new(),
+            board_status: String::new(),
+            cursor: None,
+            scroll_to_cursor: false,
+            practice: false,
+            temp_practice: false,
         }
     }
 }
+impl MinesweeperApp {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            app.statistics = eframe::get_value(storage, STATISTICS_KEY).unwrap_or_default();
+            if let Some(game) = storage.get_string(GAME_KEY).and_then(|json| Minesweeper::from_json(&json).ok()) {
+                app.resume_game(game);
+            }
+        }
+        app
+    }
+    /// Like [`start_game`](Self::start_game), for a game that may already be under way or over.
+    fn resume_game(&mut self, game: Minesweeper) {
+        self.practice = game.practice;
+        self.start_game(game);
+        self.recorded = self.game.game_over || self.game.game_won;
+    }
+    /// Replaces the current game, counting it as a loss if it was abandoned mid-play.
+    fn start_game(&mut self, game: Minesweeper) {
+        if !self.recorded {
+            self.statistics.record(&self.game);
+        }
+        self.game = game;
+        self.game.practice = self.practice;
+        self.recorded = false;
+        self.metrics = None;
+        self.hint = None;
+        self.pan = egui::Vec2::ZERO;
+        self.cursor = None;
+    }
+    /// Board keys: arrows, WASD or HJKL move the cursor; Space or Enter reveals, F flags, C
+    /// chords, R or F2 starts a new game; Ctrl+Z and Ctrl+Y undo and redo.
+    fn keyboard_input(&mut self, ctx: &egui::Context) {
+        let (undo, redo) = ctx.input_mut(|i| (
+            i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z)),
+            i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y)),
+        ));
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+        let (dx, dy, reveal, flag, chord, reset) = ctx.input(|i| {
+            if i.modifiers.command || i.modifiers.alt {
+                return (0, 0, false, false, false, false);
+            }
+            let pressed = |keys: &[egui::Key]| keys.iter().any(|&key| i.key_pressed(key));
+            let left = pressed(&[egui::Key::ArrowLeft, egui::Key::A, egui::Key::H]) as isize;
+            let right = pressed(&[egui::Key::ArrowRight, egui::Key::D, egui::Key::L]) as isize;
+            let up = pressed(&[egui::Key::ArrowUp, egui::Key::W, egui::Key::K]) as isize;
+            let down = pressed(&[egui::Key::ArrowDown, egui::Key::S, egui::Key::J]) as isize;
+            (
+                right - left,
+                down - up,
+                pressed(&[egui::Key::Space, egui::Key::Enter]),
+                pressed(&[egui::Key::F]),
+                pressed(&[egui::Key::C]),
+                pressed(&[egui::Key::R, egui::Key::F2]),
+            )
+        });
+        if dx != 0 || dy != 0 {
+            self.move_cursor(dx, dy);
+        }
+        if reset {
+            self.start_game(Minesweeper::new(self.game.config.clone()));
+            return;
+        }
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        if reveal || flag || chord {
+            self.hint = None;
+        }
+        if reveal {
+            self.game.primary_click(cursor);
+        }
+        if flag {
+            self.game.secondary_click(cursor);
+        }
+        if chord && self.game.grid[cursor].state == CellState::Revealed {
+            self.game.play(Action::Chord, cursor);
+        }
+    }
+    /// Moves the cursor one cell (see [`Board::step`]). The first move only shows the cursor
+    /// in the middle of the board.
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let grid = &self.game.grid;
+        self.scroll_to_cursor = true;
+        self.cursor = match self.cursor.filter(|&pos| grid.contains(pos)) {
+            Some(cursor) => grid.step(cursor, dx, dy).or(Some(cursor)),
+            None => grid.middle(),
+        };
+    }
+    fn undo(&mut self) {
+        if self.game.undo() {
+            self.metrics = None;
+            self.hint = None;
+        }
+    }
+    fn redo(&mut self) {
+        if self.game.redo() {
+            self.metrics = None;
+            self.hint = None;
+        }
+    }
+    fn config_name(&self, config: &GameConfig) -> String {
+        let name = if let Some(mask) = &config.mask {
+            format!("Shape \"{}\" ({} cells, {} mines)", mask.name(), mask.cell_count(), config.mine_count)
+        } else {
+            self.presets.iter()
+            .find(|(_, preset)| (preset.width, preset.height, preset.mine_count) == (config.width, config.height, config.mine_count))
+                .map_or_else(|| format!("Custom ({}×{}, {} mines)", config.width, config.height, config.mine_count), |(name, _)| name.clone())
+        };
+        let name = if config.topology == Topology::Hex {format!("{name}, hex")} else {name};
+        let name = if config.neighborhood != Neighborhood::Adjacent {format!("{name}, {}", neighborhood_name(&config.neighborhood).to_lowercase())} else {name};
+        let name = if config.wrap {format!("{name}, wrap")} else {name};
+        let name = if config.lives > 1 {format!("{name}, {} lives", config.lives)} else {name};
+        let name = if config.mines_per_cell > 1 {format!("{name}, ≤{} per cell", config.mines_per_cell)} else {name};
+        if config.no_guess {format!("{name}, no-guess")} else {name}
+    }
+    fn refresh_heat_map(&mut self) {
+        let cells: Vec<(CellState, u8)> = self.game.grid.iter().map(|cell| (cell.state, cell.flags)).collect();
+        if self.heat_map.as_ref().is_some_and(|heat| heat.seed == self.game.seed && heat.cells == cells) {
+            return;
+        }
+        let probabilities = solver::probabilities(&self.game);
+        let safest = solver::safest_cell(&probabilities);
+        self.heat_map = Some(HeatMap {seed: self.game.seed, cells, probabilities, safest});
+    }
+}
 impl eframe::App for MinesweeperApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let visuals = ctx.style().visuals.clone();
@@ -197,6 +403,12 @@ impl eframe::App for MinesweeperApp {
             },
             ..ctx.style().visuals.clone()
         });
+        if self.game.timer.is_running() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        if self.replay.is_none() && !ctx.wants_keyboard_input() {
+            self.keyboard_input(ctx);
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
@@ -206,10 +418,32 @@ impl eframe::App for MinesweeperApp {
                         egui::Color32::from_gray(180)
                     }));
                 });
-                ui.with_layout(egui::Layout::top_down(egui::Align::RIGHT), |ui| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     if ui.button(egui::RichText::new("⚙").size(20.0)).clicked() {
                         self.show_settings = true;
-                        self.temp_config = self.game.config;
+                        self.temp_config = self.game.config.clone();
+                        self.temp_practice = self.practice;
+                        self.seed_input.clear();
+                    }
+                    if ui.button(egui::RichText::new("💾").size(20.0)).on_hover_text("Save or load a game").clicked() {
+                        self.show_game_file = true;
+                        self.game_file_status.clear();
+                    }
+                    if ui.button(egui::RichText::new("📊").size(20.0)).on_hover_text("Statistics").clicked() {
+                        self.show_statistics = true;
+                    }
+                    ui.toggle_value(&mut self.show_heat_map, egui::RichText::new("🔥").size(20.0))
+                        .on_hover_text("Show the probability of a mine under each hidden cell");
+                    ui.separator();
+                    ui.label(egui::RichText::new(format!("⏱ {:03}", self.game.timer.elapsed().as_secs())).size(18.0).monospace());
+                    ui.label(egui::RichText::new(format!("💣 {:03}", self.game.mines_remaining())).size(18.0).monospace());
+                    if self.game.config.lives > 1 {
+                        ui.label(egui::RichText::new(format!("❤ {}", self.game.lives_left())).size(18.0).monospace())
+                            .on_hover_text(format!("{} of {} lives left", self.game.lives_left(), self.game.config.lives));
+                    }
+                    if self.game.no_guess_failed {
+                        ui.label(egui::RichText::new("⚠ May need guessing").color(egui::Color32::ORANGE))
+                            .on_hover_text("No board solvable without guessing was found in time; this game counts as an ordinary one");
                     }
                 });
             });
@@ -231,73 +465,422 @@ impl eframe::App for MinesweeperApp {
                     ui.label("Select difficulty preset:");
                     for (name, config) in &self.presets {
                         if ui.button(name).clicked() {
-                            self.temp_config = *config;
+                            self.temp_config.width = config.width;
+                            self.temp_config.height = config.height;
+                            self.temp_config.mine_count = config.mine_count;
+                            self.temp_config.mines_per_cell = config.mines_per_cell;
+                            self.temp_config.neighborhood = config.neighborhood.clone();
+                            self.temp_config.lives = config.lives;
+                            self.temp_config.mask = None;
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Cells:");
+                        ui.radio_value(&mut self.temp_config.topology, Topology::Square, "◼ Square");
+                        ui.radio_value(&mut self.temp_config.topology, Topology::Hex, "⬢ Hex");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Neighbors:");
+                        let config = &mut self.temp_config;
+                        egui::ComboBox::from_id_salt("neighborhood")
+                            .selected_text(neighborhood_name(&config.neighborhood))
+                            .show_ui(ui, |ui| {
+                                for neighborhood in [Neighborhood::Adjacent, Neighborhood::VonNeumann, Neighborhood::Radius2, Neighborhood::Knight] {
+                                    let name = neighborhood_name(&neighborhood);
+                                    ui.selectable_value(&mut config.neighborhood, neighborhood, name);
+                                }
+                                let custom = matches!(config.neighborhood, Neighborhood::Custom(_));
+                                if ui.selectable_label(custom, "Custom").clicked() && !custom {
+                                    // Start editing from the current pattern.
+                                    config.neighborhood = Neighborhood::Custom(config.neighborhood.offsets(config.topology, false));
+                                }
+                            });
+                    });
+                    if let Neighborhood::Custom(offsets) = &mut self.temp_config.neighborhood {
+                        // Click cells around the center (◆) to toggle them.
+                        let reach = Neighborhood::MAX_REACH;
+                        egui::Grid::new("custom_neighborhood").spacing([2.0, 2.0]).show(ui, |ui| {
+                            for dy in -reach..=reach {
+                                for dx in -reach..=reach {
+                                    if (dx, dy) == (0, 0) {
+                                        ui.add_enabled(false, egui::Button::new("◆").min_size(egui::vec2(20.0, 20.0)));
+                                        continue;
+                                    }
+                                    let selected = offsets.contains(&(dx, dy));
+                                    if ui.add(egui::Button::new("").selected(selected).min_size(egui::vec2(20.0, 20.0))).clicked() {
+                                        if selected {
+                                            offsets.retain(|&offset| offset != (dx, dy));
+                                        } else {
+                                            offsets.push((dx, dy));
+                                        }
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    // Wrapped hex rows alternate their shift, so the row count must be even. Rectangles
+                    // grow a row below; a shape's height is fixed, so an odd one cannot wrap.
+                    let config = &mut self.temp_config;
+                    let odd_hex_shape = config.topology == Topology::Hex && config.mask.as_ref().is_some_and(|mask| mask.height() % 2 == 1);
+                    config.wrap &= !odd_hex_shape;
+                    ui.add_enabled(!odd_hex_shape, egui::Checkbox::new(&mut config.wrap, "Wrap around edges"))
+                        .on_hover_text("Opposite edges touch, like a torus; drag the board to pan")
+                        .on_disabled_hover_text("Hex shapes need an even number of rows to wrap");
+                    ui.checkbox(&mut self.temp_config.question_marks, "Question marks").on_hover_text("Secondary clicks cycle a cell through flag, ? and hidden");
+                    ui.checkbox(&mut self.temp_practice, "Practice mode").on_hover_text("Mine hits and finished games can be undone; games are not recorded in statistics");
+                    ui.checkbox(&mut self.temp_config.no_guess, "No-guess board").on_hover_text("Only generate boards that can be solved without guessing");
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Board:");
+                        ui.add(egui::TextEdit::singleline(&mut self.board_path).hint_text("board.txt or board.json"));
+                        if ui.button("📂 Open board…").on_hover_text("A fixed mine layout (* = mine, . = empty, space = hole) or JSON from minesweeper-cli").clicked() {
+                            match Layout::load(&self.board_path) {
+                                Ok(mut layout) => {
+                                    layout.config.lives = self.temp_config.lives;
+                                    layout.config.question_marks = self.temp_config.question_marks;
+                                    self.board_status.clear();
+                                    self.practice = self.temp_practice;
+                                    self.start_game(layout.game());
+                                    self.show_settings = false;
+                                }
+                                Err(e) => self.board_status = format!("Could not open board: {e}"),
+                            }
                         }
+                    });
+                    if !self.board_status.is_empty() {
+                        ui.label(&self.board_status);
                     }
                     ui.separator();
                     ui.label("Custom settings:");
-                    ui.add(egui::Slider::new(&mut self.temp_config.width, 6..=50).text("Width"));
-                    ui.add(egui::Slider::new(&mut self.temp_config.height, 6..=50).text("Height"));
-                    ui.add(egui::Slider::new(&mut self.temp_config.mine_count, 1..=(self.temp_config.width * self.temp_config.height-9)).text("Mines"));
+                    ui.horizontal(|ui| {
+                        ui.label("Shape:");
+                        ui.add(egui::TextEdit::singleline(&mut self.mask_path).hint_text("mask.txt or mask.png"));
+                        if ui.button("Load").on_hover_text("ASCII art (# = cell, . or space = hole) or a PNG (dark pixels = cells)").clicked() {
+                            match Mask::load(&self.mask_path) {
+                                Ok(mask) => {
+                                    self.mask_status = format!("Loaded {} ({} cells)", mask.name(), mask.cell_count());
+                                    self.temp_config.width = mask.width();
+                                    self.temp_config.height = mask.height();
+                                    self.temp_config.mask = Some(mask);
+                                }
+                                Err(e) => self.mask_status = format!("Could not load mask: {e}"),
+                            }
+                        }
+                        if self.temp_config.mask.is_some() && ui.button("✖ Rectangle").clicked() {
+                            self.temp_config.mask = None;
+                            self.mask_status.clear();
+                        }
+                    });
+                    if !self.mask_status.is_empty() {
+                        ui.label(&self.mask_status);
+                    }
+                    let rectangle = self.temp_config.mask.is_none();
+                    ui.add_enabled(rectangle, egui::Slider::new(&mut self.temp_config.width, 6..=MAX_BOARD_SIZE).logarithmic(true).text("Width"));
+                    ui.add_enabled(rectangle, egui::Slider::new(&mut self.temp_config.height, 6..=MAX_BOARD_SIZE).logarithmic(true).text("Height"));
+                    if rectangle && self.temp_config.wrap && self.temp_config.topology == Topology::Hex && self.temp_config.height % 2 == 1 {
+                        self.temp_config.height += 1;
+                    }
+                    ui.add(egui::Slider::new(&mut self.temp_config.lives, 1..=10).text("Lives"))
+                        .on_hover_text("Mines you can hit before the game is lost");
+                    ui.add(egui::Slider::new(&mut self.temp_config.mines_per_cell, 1..=GameConfig::MAX_MINES_PER_CELL).text("Mines per cell"))
+                        .on_hover_text("Cells can hold several mines; numbers count mines and flags cycle up to this many");
+                    let max_mines = self.temp_config.max_mines();
+                    if max_mines > 0 {
+                        self.temp_config.mine_count = self.temp_config.mine_count.clamp(1, max_mines);
+                        ui.add(egui::Slider::new(&mut self.temp_config.mine_count, 1..=max_mines).logarithmic(true).text("Mines"));
+                    } else {
+                        ui.label("⚠ No room for mines: the first click keeps its neighborhood free");
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Current seed: {}", self.game.seed));
+                        if ui.button("📋 Copy").clicked() {
+                            ui.ctx().copy_text(self.game.seed.to_string());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Seed:");
+                        ui.add(egui::TextEdit::singleline(&mut self.seed_input).hint_text("random"));
+                    });
                     ui.horizontal(|ui| {
                         if ui.button("Cancel").clicked() {
                             self.show_settings = false;
                         }
-                        if ui.button("Apply").clicked() {
-                            self.game = Minesweeper::new(self.temp_config);
+                        if ui.add_enabled(max_mines > 0, egui::Button::new("Apply")).clicked() {
+                            let game = match self.seed_input.trim().parse() {
+                                Ok(seed) => Minesweeper::with_seed(self.temp_config.clone(), seed),
+                                Err(_) => Minesweeper::new(self.temp_config.clone()),
+                            };
+                            self.practice = self.temp_practice;
+                            self.start_game(game);
                             self.show_settings = false;
                         }
                     });
                 });
         }
+        if self.show_game_file {
+            let file_window_ctx=ctx.clone();
+            file_window_ctx.set_visuals(egui::Visuals {
+                window_fill: if visuals.dark_mode {
+                    egui::Color32::from_gray(30)
+                } else {
+                    egui::Color32::from_gray(190)
+                },
+                ..file_window_ctx.style().visuals.clone()
+            });
+            let mut open = true;
+            egui::Window::new("Save / Load Game")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(&file_window_ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("File:");
+                        ui.text_edit_singleline(&mut self.game_path);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("💾 Save game").clicked() {
+                            self.game_file_status = match self.game.save(&self.game_path) {
+                                Ok(()) => format!("Saved to {}", self.game_path),
+                                Err(e) => format!("Could not save: {e}"),
+                            };
+                        }
+                        if ui.button("📂 Load game").clicked() {
+                            self.game_file_status = match Minesweeper::load(&self.game_path) {
+                                Ok(game) => {
+                                    self.resume_game(game);
+                                    format!("Loaded {}", self.game_path)
+                                }
+                                Err(e) => format!("Could not load: {e}"),
+                            };
+                        }
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Replay:");
+                        ui.text_edit_singleline(&mut self.replay_path);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("🎬 Save replay").clicked() {
+                            self.game_file_status = match Replay::from_game(&self.game) {
+                                Some(replay) => match replay.save(&self.replay_path) {
+                                    Ok(()) => format!("Saved replay to {}", self.replay_path),
+                                    Err(e) => format!("Could not save replay: {e}"),
+                                },
+                                None => "No moves to save yet".to_string(),
+                            };
+                        }
+                        if ui.button("▶ Open replay").clicked() {
+                            self.game_file_status = match Replay::load(&self.replay_path) {
+                                Ok(replay) => {
+                                    self.replay = Some(ReplayViewer::new(replay));
+                                    format!("Playing {}", self.replay_path)
+                                }
+                                Err(e) => format!("Could not load replay: {e}"),
+                            };
+                        }
+                    });
+                    if !self.game_file_status.is_empty() {
+                        ui.label(&self.game_file_status);
+                    }
+                });
+            self.show_game_file &= open;
+        }
+        if self.show_statistics {
+            let stats_window_ctx=ctx.clone();
+            stats_window_ctx.set_visuals(egui::Visuals {
+                window_fill: if visuals.dark_mode {
+                    egui::Color32::from_gray(30)
+                } else {
+                    egui::Color32::from_gray(190)
+                },
+                ..stats_window_ctx.style().visuals.clone()
+            });
+            let mut open = true;
+            egui::Window::new("Statistics")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(&stats_window_ctx, |ui| {
+                    if self.statistics.iter().next().is_none() {
+                        ui.label("No games played yet.");
+                        return;
+                    }
+                    egui::Grid::new("statistics_grid").striped(true).show(ui, |ui| {
+                        for header in ["Board", "Played", "Won", "Win %", "Streak", "Best streak", "Best time", "Average time", "Best 3BV/s"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        let format_time = |time: Option<std::time::Duration>| time.map_or("-".to_string(), |t| format!("{:.3} s", t.as_secs_f64()));
+                        for (config, record) in self.statistics.iter() {
+                            ui.label(self.config_name(config));
+                            ui.label(record.played.to_string());
+                            ui.label(record.won.to_string());
+                            ui.label(format!("{:.1}", record.win_rate() * 100.0));
+                            ui.label(record.current_streak.to_string());
+                            ui.label(record.best_streak.to_string());
+                            ui.label(format_time(record.best_time));
+                            ui.label(format_time(record.average_time()));
+                            ui.label(record.best_bbbv_per_second.map_or("-".to_string(), |rate| format!("{rate:.2}")));
+                            ui.end_row();
+                        }
+                    });
+                    ui.separator();
+                    egui::CollapsingHeader::new("Recent games").show(ui, |ui| {
+                        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                            egui::Grid::new("history_grid").striped(true).show(ui, |ui| {
+                                for header in ["Board", "Result", "Time", "3BV", "3BV/s", "IOE", "Correctness", "Hints", "Lives lost"] {
+                                    ui.strong(header);
+                                }
+                                ui.end_row();
+                                for (config, result) in self.statistics.iter()
+                                    .flat_map(|(config, record)| record.history.iter().map(move |result| (config, result)))
+                                    .collect::<Vec<_>>().into_iter().rev() {
+                                    let metrics = &result.metrics;
+                                    ui.label(self.config_name(config));
+                                    ui.label(if result.won {"Won"} else {"Lost"});
+                                    ui.label(format!("{:.3} s", metrics.time.as_secs_f64()));
+                                    ui.label(format!("{}/{}", metrics.solved_bbbv, metrics.bbbv));
+                                    ui.label(format!("{:.2}", metrics.bbbv_per_second()));
+                                    ui.label(format!("{:.2}", metrics.ioe()));
+                                    ui.label(format!("{:.0}%", metrics.correctness() * 100.0));
+                                    ui.label(result.hints_used.to_string());
+                                    ui.label(result.lives_lost.to_string());
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    });
+                    ui.separator();
+                    if ui.button("Reset statistics").clicked() {
+                        self.statistics = Statistics::default();
+                    }
+                });
+            self.show_statistics &= open;
+        }
+        if let Some(viewer) = &mut self.replay && viewer.playing {
+            viewer.advance(ctx.input(|i| i.stable_dt));
+            ctx.request_repaint();
+        }
+        if let Some(viewer) = &mut self.replay {
+            let mut close = false;
+            egui::TopBottomPanel::bottom("replay_panel").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("⏮").clicked() {
+                        viewer.seek(0);
+                    }
+                    if ui.button(if viewer.playing {"⏸"} else {"▶"}).clicked() {
+                        if viewer.step == viewer.replay.moves.len() {
+                            viewer.seek(0);
+                        }
+                        viewer.playing = !viewer.playing;
+                    }
+                    if ui.button("⏭").clicked() {
+                        viewer.seek(viewer.replay.moves.len());
+                    }
+                    let mut step = viewer.step;
+                    if ui.add(egui::Slider::new(&mut step, 0..=viewer.replay.moves.len()).text("Move")).changed() {
+                        viewer.seek(step);
+                    }
+                    ui.label(format!("⏱ {:.3} / {:.3} s", viewer.clock.min(viewer.replay.duration()).as_secs_f64(), viewer.replay.duration().as_secs_f64()));
+                    if ui.button("✖ Exit replay").clicked() {
+                        close = true;
+                    }
+                });
+            });
+            if close {
+                self.replay = None;
+            }
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                if ui.button(egui::RichText::new("🔄 Reset Game").size(15.0)).clicked() {
-                    self.game.reset();
+                let reset = ui.button(egui::RichText::new("🔄 Reset Game").size(15.0));
+                if reset.clicked() {
+                    self.start_game(Minesweeper::new(self.game.config.clone()));
+                }
+                let hint_rect = egui::Rect::from_min_size(
+                    reset.rect.right_top() + egui::Vec2::new(8.0, 0.0),
+                    egui::Vec2::new(80.0, reset.rect.height())
+                );
+                if ui.put(hint_rect, egui::Button::new(egui::RichText::new("💡 Hint").size(15.0))).clicked() {
+                    self.hint = self.game.hint();
+                }
+                let undo_rect = egui::Rect::from_min_size(
+                    reset.rect.left_top() - egui::Vec2::new(88.0, 0.0),
+                    egui::Vec2::new(80.0, reset.rect.height())
+                );
+                let can_undo = self.game.can_undo() && self.replay.is_none();
+                let undo = |ui: &mut egui::Ui| ui.add_enabled(can_undo, egui::Button::new(egui::RichText::new("↶ Undo").size(15.0)));
+                if ui.put(undo_rect, undo).on_hover_text("Ctrl+Z").clicked() {
+                    self.undo();
+                }
+                let can_redo = self.game.can_redo() && self.replay.is_none();
+                let redo = |ui: &mut egui::Ui| ui.add_enabled(can_redo, egui::Button::new(egui::RichText::new("↷ Redo").size(15.0)));
+                if ui.put(hint_rect.translate(egui::Vec2::new(88.0, 0.0)), redo).on_hover_text("Ctrl+Y").clicked() {
+                    self.redo();
                 }
             });
+            if self.show_heat_map && self.replay.is_none() {
+                self.refresh_heat_map();
+            }
+            let config = self.replay.as_ref().map_or(&self.game.config, |viewer| &viewer.board.config).clone();
             let available_size = ui.available_size();
-            let pixel_size_x=(available_size.x*0.95)/self.game.config.width as f32;
-            let pixel_size_y=(available_size.y*0.95)/self.game.config.height as f32;
-            let cell_pixel_size = pixel_size_x.min(pixel_size_y);
-            let total_width = self.game.config.width as f32 * cell_pixel_size;
-            let total_height = self.game.config.height as f32 * cell_pixel_size;
+            let cell_pixel_size = BoardGeometry::fit(&config, available_size * 0.95).max(MIN_CELL_SIZE);
             ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                let (response, painter) = ui.allocate_painter(
-                    egui::Vec2::new(total_width, total_height),
-                    egui::Sense::click_and_drag()
-                );
-                let pos = response.rect.min;
-                let hover_pos = response.hover_pos();
-                let mut hover_x = None;
-                let mut hover_y = None;
-                if let Some(pos) = hover_pos {
-                    let x = ((pos.x - response.rect.min.x) / cell_pixel_size).floor() as usize;
-                    let y = ((pos.y - response.rect.min.y) / cell_pixel_size).floor() as usize;
-                    if x < self.game.config.width && y < self.game.config.height {
-                        hover_x = Some(x);
-                        hover_y = Some(y);
-                    }
-                }
-                for y in 0..self.game.config.height {
-                    for x in 0..self.game.config.width {
-                        let cell = &self.game.grid[y][x];
-                        let rect = egui::Rect::from_min_size(
-                            pos + egui::Vec2::new(x as f32 * cell_pixel_size, y as f32 * cell_pixel_size),
-                            egui::Vec2::splat(cell_pixel_size)
+                egui::ScrollArea::both().show(ui, |ui| {
+                    let game = self.replay.as_ref().map_or(&self.game, |viewer| &viewer.board);
+                    let heat_map = self.heat_map.as_ref().filter(|_| self.show_heat_map && self.replay.is_none());
+                    let (response, painter) = ui.allocate_painter(
+                        BoardGeometry::board_size(&config, cell_pixel_size),
+                        egui::Sense::click_and_drag()
+                    );
+                    let mut geometry = BoardGeometry {
+                        origin: response.rect.min,
+                        cell_size: cell_pixel_size,
+                        topology: config.topology,
+                        width: config.width,
+                        height: config.height,
+                        pan: Pos::default(),
+                    };
+                    if config.wrap {
+                        // Dragging slides the torus under the viewport by whole cells; hex boards
+                        // move by two rows so row shifts stay aligned.
+                        if response.dragged_by(egui::PointerButton::Primary) {
+                            self.pan -= response.drag_delta();
+                        }
+                        let row_step = if config.topology == Topology::Hex {2} else {1};
+                        let rows = (self.pan.y / (geometry.row_height() * row_step as f32)).round() as isize * row_step;
+                        let columns = (self.pan.x / cell_pixel_size).round() as isize;
+                        geometry.pan = Pos::new(
+                            columns.rem_euclid(config.width as isize) as usize,
+                            rows.rem_euclid(config.height as isize) as usize
                         );
+                    }
+                    // Only the cells inside the scroll viewport are painted.
+                    let visible = ui.clip_rect().intersect(response.rect);
+                    let hovered = response.hover_pos().and_then(|point| geometry.cell_at(point)).filter(|&pos| game.grid.contains(pos));
+                    for cell_pos in geometry.cells_in(visible).filter(|&pos| game.grid.contains(pos)) {
+                        let cell = &game.grid[cell_pos];
+                        let center = geometry.center(cell_pos);
+                        let outline = geometry.outline(cell_pos, 0.0);
                         let mut bg_color = match cell.state {
-                            CellState::Hidden => if visuals.dark_mode {
+                            CellState::Hidden | CellState::Questioned => if visuals.dark_mode {
                                 egui::Color32::from_gray(60)
                             } else {
                                 egui::Color32::from_gray(220)
                             },
+                            CellState::Flagged | CellState::Revealed if cell.exploded => if visuals.dark_mode {
+                                egui::Color32::from_rgb(200, 110, 30)
+                            } else {
+                                egui::Color32::from_rgb(255, 190, 90)
+                            },
                             CellState::Flagged => if visuals.dark_mode {
                                 egui::Color32::from_rgb(150, 70, 70)
                             } else {
                                 egui::Color32::from_rgb(250, 180, 180)
                             },
-                            CellState::Revealed => if cell.is_mine {
+                            CellState::Revealed => if cell.is_mine() {
                                 if visuals.dark_mode {
                                     egui::Color32::from_rgb(180, 70, 70)
                                 } else {
@@ -311,33 +894,52 @@ impl eframe::App for MinesweeperApp {
                                 }
                             }
                         };
-                        if Some(x) == hover_x && Some(y) == hover_y && cell.state == CellState::Hidden {
+                        if hovered == Some(cell_pos) && cell.state.is_hidden() {
                             bg_color = if visuals.dark_mode {
                                 bg_color.gamma_multiply(1.2)
                             } else {
                                 bg_color.gamma_multiply(0.8)
                             };
                         }
-                        painter.rect_filled(rect, 0.0, bg_color);
-                        painter.rect_stroke(
-                            rect,
-                            0.0,
-                            egui::Stroke::new(0.5, egui::Color32::from_gray(100)),
-                            egui::StrokeKind::Outside
-                        );
+                        painter.add(egui::Shape::convex_polygon(outline.clone(), bg_color, egui::Stroke::NONE));
+                        if let Some(p) = heat_map.and_then(|heat| heat.probabilities[cell_pos]) {
+                            painter.add(egui::Shape::convex_polygon(
+                                outline.clone(),
+                                egui::Color32::from_rgba_unmultiplied((255.0 * p) as u8, (255.0 * (1.0 - p)) as u8, 0, 110),
+                                egui::Stroke::NONE
+                            ));
+                            if cell_pixel_size >= 28.0 {
+                                painter.text(
+                                    center,
+                                    egui::Align2::CENTER_CENTER,
+                                    format!("{:.0}%", p * 100.0),
+                                    egui::FontId::proportional(cell_pixel_size * 0.3),
+                                    egui::Color32::WHITE
+                                );
+                            }
+                        }
+                        painter.add(egui::Shape::closed_line(outline, egui::Stroke::new(0.5, egui::Color32::from_gray(100))));
+                        // Multi-mine cells show their flag or mine count next to the icon.
                         let text = match cell.state {
-                            CellState::Hidden => "",
-                            CellState::Flagged => "🚩",
+                            CellState::Hidden => String::new(),
+                            CellState::Flagged | CellState::Revealed if cell.exploded && cell.mines > 1 => format!("💥{}", cell.mines),
+                            CellState::Flagged | CellState::Revealed if cell.exploded => "💥".to_string(),
+                            CellState::Flagged if cell.flags > 1 => format!("🚩{}", cell.flags),
+                            CellState::Flagged => "🚩".to_string(),
+                            CellState::Questioned => "❓".to_string(),
                             CellState::Revealed => {
-                                if cell.is_mine {
-                                    "💣"
+                                if cell.mines > 1 {
+                                    format!("💣{}", cell.mines)
+                                } else if cell.is_mine() {
+                                    "💣".to_string()
                                 } else if cell.adjacent_mines>0{
-                                    &cell.adjacent_mines.to_string() as &str
+                                    cell.adjacent_mines.to_string()
                                 }else{
-                                    ""
+                                    String::new()
                                 }
                             }
                         };
+                        let font_scale = if text.chars().count() > 1 {0.5} else {0.7};
                         let text_color =match cell.state{
                             CellState::Revealed => match cell.adjacent_mines {
                                 1 => egui::Color32::from_rgb(100, 150, 255),
@@ -353,112 +955,120 @@ impl eframe::App for MinesweeperApp {
                             _ => egui::Color32::WHITE
                         };
                         painter.text(
-                            rect.center(),
+                            center,
                             egui::Align2::CENTER_CENTER,
                             text,
-                            egui::FontId::monospace(cell_pixel_size*0.7),
+                            egui::FontId::monospace(cell_pixel_size*font_scale),
                             text_color
                         );
                     }
-                }
-                if response.clicked() {
-                    if let Some(pos) = response.interact_pointer_pos() {
-                        let x = ((pos.x - response.rect.min.x) / cell_pixel_size).floor() as usize;
-                        let y = ((pos.y - response.rect.min.y) / cell_pixel_size).floor() as usize;
-                        if x < self.game.config.width && y < self.game.config.height {
-                            if self.game.grid[y][x].state!=CellState::Flagged{
-                                self.game.reveal_cell(x, y);
-                            }
-                            if self.game.grid[y][x].adjacent_mines!=0&&self.game.grid[y][x].state==CellState::Revealed{
-                                let mut near_flagged = 0;
-                                for dx in -1..=1{
-                                    for dy in -1..=1{
-                                        let nx=x as i32 + dx;
-                                        let ny=y as i32 + dy;
-                                        if nx<0||nx>=self.game.config.width as i32||ny<0||ny>=self.game.config.height as i32{
-                                            continue;
-                                        }
-                                        if self.game.grid[ny as usize][nx as usize].state==CellState::Flagged{
-                                            near_flagged+=1;
-                                        }
-                                    }
-                                }
-                                if near_flagged==self.game.grid[y][x].adjacent_mines{
-                                    for dx in -1..=1{
-                                        for dy in -1..=1{
-                                            let nx=x as i32 + dx;
-                                            let ny=y as i32 + dy;
-                                            if nx<0||nx>=self.game.config.width as i32||ny<0||ny>=self.game.config.height as i32{
-                                                continue;
-                                            }
-                                            if self.game.grid[ny as usize][nx as usize].state!=CellState::Flagged{
-                                                self.game.reveal_cell(nx as usize, ny as usize);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                    for seam in geometry.seams(response.rect) {
+                        painter.line_segment(seam, egui::Stroke::new(2.0, egui::Color32::from_rgb(120, 170, 255)));
+                    }
+                    if let Some(viewer) = &self.replay && let Some(last) = viewer.step.checked_sub(1) {
+                        painter.add(egui::Shape::closed_line(
+                            geometry.outline(viewer.replay.moves[last].pos(), 1.5),
+                            egui::Stroke::new(3.0, egui::Color32::from_rgb(200, 120, 255))
+                        ));
+                    }
+                    if self.replay.is_some() {
+                        return;
+                    }
+                    if let Some(safest) = heat_map.and_then(|heat| heat.safest) {
+                        painter.add(egui::Shape::closed_line(
+                            geometry.outline(safest, 1.25),
+                            egui::Stroke::new(2.5, egui::Color32::from_rgb(255, 220, 0))
+                        ));
+                    }
+                    if let Some(hint) = self.hint {
+                        let (hint_pos, color) = match hint {
+                            Hint::Safe(pos) => (pos, egui::Color32::from_rgb(80, 200, 255)),
+                            Hint::Mine(pos) => (pos, egui::Color32::from_rgb(255, 80, 80)),
+                            Hint::Guess(pos, _) => (pos, egui::Color32::from_rgb(255, 160, 0)),
+                        };
+                        painter.add(egui::Shape::closed_line(geometry.outline(hint_pos, 1.5), egui::Stroke::new(3.0, color)));
+                    }
+                    if let Some(cursor) = self.cursor.filter(|&pos| game.grid.contains(pos)) {
+                        let outline = geometry.outline(cursor, 2.0);
+                        if std::mem::take(&mut self.scroll_to_cursor) {
+                            ui.scroll_to_rect(egui::Rect::from_points(&outline).expand(cell_pixel_size), None);
                         }
+                        let color = if visuals.dark_mode {egui::Color32::WHITE} else {egui::Color32::BLACK};
+                        painter.add(egui::Shape::closed_line(outline, egui::Stroke::new(3.0, color)));
                     }
-                }
-                if response.secondary_clicked() {
-                    if let Some(pos) = response.interact_pointer_pos() {
-                        let x = ((pos.x - response.rect.min.x) / cell_pixel_size).floor() as usize;
-                        let y = ((pos.y - response.rect.min.y) / cell_pixel_size).floor() as usize;
-                        if x < self.game.config.width && y < self.game.config.height {
-                            self.game.toggle_flag(x, y);
+                    if response.clicked() || response.secondary_clicked() {
+                        self.hint = None;
+                    }
+                    let clicked = response.interact_pointer_pos().and_then(|point| geometry.cell_at(point));
+                    if response.clicked() && let Some(cell_pos) = clicked {
+                        self.game.primary_click(cell_pos);
+                    }
+                    if response.secondary_clicked() && let Some(cell_pos) = clicked {
+                        self.game.secondary_click(cell_pos);
+                    }
+                    if (self.game.game_over || self.game.game_won) && !self.recorded {
+                        self.statistics.record(&self.game);
+                        self.recorded = true;
+                    }
+                    if self.game.game_over || self.game.game_won {
+                        let rect = visible;
+                        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(150));
+                        let (text, color) = if self.game.game_over {
+                            ("💥 Game Over! You hit a mine! 💥", egui::Color32::from_rgb(255, 100, 100))
+                        } else {
+                            ("🎉 Congratulations! You won! 🎉", egui::Color32::from_rgb(100, 255, 100))
+                        };
+                        painter.text(rect.center(), egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(24.0), color);
+                        let metrics = *self.metrics.get_or_insert_with(|| Metrics::from_game(&self.game).unwrap_or_default());
+                        let mut lines = vec![
+                            format!("⏱ {:.3} s", self.game.timer.elapsed().as_secs_f64()),
+                            format!(
+                                "3BV {}/{} · {:.2} 3BV/s · IOE {:.2} · Correctness {:.0}%",
+                                metrics.solved_bbbv,
+                                metrics.bbbv,
+                                metrics.bbbv_per_second(),
+                                metrics.ioe(),
+                                metrics.correctness() * 100.0
+                            ),
+                            format!("Clicks: {} left, {} right, {} chord", metrics.left_clicks, metrics.right_clicks, metrics.chord_clicks),
+                        ];
+                        if self.game.hints_used > 0 {
+                            lines.push(format!("💡 Hints used: {}", self.game.hints_used));
                         }
-                        if self.game.grid[y][x].adjacent_mines!=0&&self.game.grid[y][x].state==CellState::Revealed{
-                            let mut near_unrevealed = 0;
-                            for dx in -1..=1{
-                                for dy in -1..=1{
-                                    let nx=x as i32 + dx;
-                                    let ny=y as i32 + dy;
-                                    if nx<0||nx>=self.game.config.width as i32||ny<0||ny>=self.game.config.height as i32{
-                                        continue;
-                                    }
-                                    if self.game.grid[ny as usize][nx as usize].state!=CellState::Revealed{
-                                        near_unrevealed+=1;
-                                    }
-                                }
-                            }
-                            if near_unrevealed==self.game.grid[y][x].adjacent_mines{
-                                for dx in -1..=1{
-                                    for dy in -1..=1{
-                                        let nx=x as i32 + dx;
-                                        let ny=y as i32 + dy;
-                                        if nx<0||nx>=self.game.config.width as i32||ny<0||ny>=self.game.config.height as i32{
-                                            continue;
-                                        }
-                                        if self.game.grid[ny as usize][nx as usize].state!=CellState::Revealed&&self.game.grid[ny as usize][nx as usize].state!=CellState::Flagged{
-                                            self.game.toggle_flag(nx as usize, ny as usize);
-                                        }
-                                    }
-                                }
-                            }
+                        if self.game.config.lives > 1 {
+                            lines.push(format!("❤ Lives lost: {} of {}", self.game.lives_lost, self.game.config.lives));
+                        }
+                        if self.game.can_undo() {
+                            lines.push("↶ Practice mode: Ctrl+Z takes the last move back".to_string());
+                        }
+                        for (i, line) in lines.into_iter().enumerate() {
+                            painter.text(
+                                egui::pos2(rect.center().x, rect.center().y - 32.0 - 24.0 * i as f32),
+                                egui::Align2::CENTER_CENTER,
+                                line,
+                                egui::FontId::proportional(if i == 0 {18.0} else {16.0}),
+                                egui::Color32::WHITE
+                            );
+                        }
+                        let button_rect = egui::Rect::from_center_size(
+                            egui::pos2(rect.center().x, rect.center().y + 50.0),
+                            egui::Vec2::new(150.0, 40.0)
+                        );
+                        if ui.put(button_rect, egui::Button::new("🔄 Restart")).clicked() {
+                            self.start_game(Minesweeper::new(self.game.config.clone()));
+                        }
+                        if ui.put(button_rect.translate(egui::Vec2::new(0.0, 50.0)), egui::Button::new("🎬 Watch replay")).clicked() {
+                            self.replay = Replay::from_game(&self.game).map(ReplayViewer::new);
                         }
                     }
-                }
-                if self.game.game_over || self.game.game_won {
-                    let rect = response.rect;
-                    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(150));
-                    let (text, color) = if self.game.game_over {
-                        ("💥 Game Over! You hit a mine! 💥", egui::Color32::from_rgb(255, 100, 100))
-                    } else {
-                        ("🎉 Congratulations! You won! 🎉", egui::Color32::from_rgb(100, 255, 100))
-                    };
-                    painter.text(rect.center(), egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(24.0), color);
-                    let button_rect = egui::Rect::from_center_size(
-                        egui::pos2(rect.center().x, rect.center().y + 50.0),
-                        egui::Vec2::new(150.0, 40.0)
-                    );
-                    if ui.put(button_rect, egui::Button::new("🔄 Restart")).clicked() {
-                        self.game.reset();
-                    }
-                }
+                });
             });
         });
     }
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STATISTICS_KEY, &self.statistics);
+        storage.set_string(GAME_KEY, self.game.to_json());
+    }
 }
 fn main() {
     let options = eframe::NativeOptions {
@@ -474,7 +1084,7 @@ fn main() {
                 panel_fill: egui::Color32::TRANSPARENT,
                 ..Default::default()
             });
-            Ok(Box::new(MinesweeperApp::default()))
+            Ok(Box::new(MinesweeperApp::new(cc)))
         })
     ).expect("Error occurred while initializing the main window");
 }
\ No newline at end of file
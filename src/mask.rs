@@ -0,0 +1,130 @@
+//! Board shapes: which cells of the `width`×`height` rectangle exist.
+//!
+//! Masks are drawn as ASCII art, one line per row. Spaces, `.`, `_`, `-` and `0` are holes;
+//! any other character is a cell. Short lines are padded with holes, and empty margins are
+//! cropped:
+//!
+//! ```text
+//!  ##   ##
+//! #### ####
+//! #########
+//!  #######
+//!    ###
+//!     #
+//! ```
+//!
+//! With the `png` feature, images work too: opaque dark pixels (alpha and luminance both
+//! past the midpoint) are cells, everything else is a hole.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Pos;
+
+/// A board shape. Serializes as `{"name": ..., "rows": ["#.#", ...]}` with `#` for cells and
+/// `.` for holes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "MaskRows", into = "MaskRows")]
+pub struct Mask {name: String, width: usize, height: usize, cells: Vec<bool>}
+impl Mask {
+    /// Reads an ASCII mask as described in the [module docs](self).
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let rows: Vec<Vec<bool>> = text.lines()
+            .map(|line| line.chars().map(|c| !matches!(c, ' ' | '.' | '_' | '-' | '0' | '\t')).collect())
+            .collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let cells = rows.iter()
+            .flat_map(|row| (0..width).map(|x| row.get(x).copied().unwrap_or(false)))
+            .collect();
+        Self::cropped(name, width, rows.len(), cells)
+    }
+    /// Reads a PNG mask as described in the [module docs](self).
+    #[cfg(feature = "png")]
+    pub fn from_png(name: &str, bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(io::Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut buffer = vec![0; reader.output_buffer_size().ok_or("image is too large")?];
+        let info = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+        let channels = info.color_type.samples();
+        let (width, height) = (info.width as usize, info.height as usize);
+        let cells = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| {
+            let pixel = &buffer[y * info.line_size + x * channels..][..channels];
+            let (luminance, alpha) = match pixel {
+                [l] => (*l as u32, 255),
+                [l, a] => (*l as u32, *a),
+                [r, g, b] => ((*r as u32 * 3 + *g as u32 * 6 + *b as u32) / 10, 255),
+                [r, g, b, a, ..] => ((*r as u32 * 3 + *g as u32 * 6 + *b as u32) / 10, *a),
+                [] => (255, 0),
+            };
+            alpha >= 128 && luminance < 128
+        }).collect();
+        Self::cropped(name, width, height, cells)
+    }
+    /// Loads a mask file: `.png` files as images, anything else as ASCII. The file stem
+    /// becomes the mask's name.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")) {
+            #[cfg(feature = "png")]
+            return Self::from_png(&name, &fs::read(path)?).map_err(invalid);
+            #[cfg(not(feature = "png"))]
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "PNG masks need the `png` feature"));
+        }
+        Self::parse(&name, &fs::read_to_string(path)?).map_err(invalid)
+    }
+    /// Shrinks the rectangle to the bounding box of the cells.
+    fn cropped(name: &str, width: usize, height: usize, cells: Vec<bool>) -> Result<Self, String> {
+        let set = |x: usize, y: usize| cells[y * width + x];
+        let columns: Vec<usize> = (0..width).filter(|&x| (0..height).any(|y| set(x, y))).collect();
+        let rows: Vec<usize> = (0..height).filter(|&y| (0..width).any(|x| set(x, y))).collect();
+        let (Some(&left), Some(&right), Some(&top), Some(&bottom)) = (columns.first(), columns.last(), rows.first(), rows.last()) else {
+            return Err("mask has no cells".to_string());
+        };
+        Ok(Self {
+            name: name.to_string(),
+            width: right - left + 1,
+            height: bottom - top + 1,
+            cells: (top..=bottom).flat_map(|y| (left..=right).map(move |x| (x, y))).map(|(x, y)| set(x, y)).collect(),
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// Whether `pos` is a cell rather than a hole or off the rectangle.
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x < self.width && pos.y < self.height && self.cells[pos.y * self.width + pos.x]
+    }
+    /// Number of cells.
+    pub fn cell_count(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+}
+
+/// Serialized form of [`Mask`].
+#[derive(Serialize, Deserialize)]
+struct MaskRows {name: String, rows: Vec<String>}
+impl From<Mask> for MaskRows {
+    fn from(mask: Mask) -> Self {
+        let rows = mask.cells.chunks(mask.width.max(1))
+            .map(|row| row.iter().map(|&cell| if cell {'#'} else {'.'}).collect())
+            .collect();
+        Self {name: mask.name, rows}
+    }
+}
+impl TryFrom<MaskRows> for Mask {
+    type Error = String;
+    fn try_from(repr: MaskRows) -> Result<Self, String> {
+        Self::parse(&repr.name, &repr.rows.join("\n"))
+    }
+}
@@ -0,0 +1,118 @@
+//! Board difficulty and player efficiency figures used by competitive players.
+//!
+//! * **3BV** (Bechtel's Board Benchmark Value): the minimum number of left clicks needed to
+//!   clear the board without flags — one per opening (connected area of zeros, which opens
+//!   in one click) plus one per safe cell not bordering any opening.
+//! * **3BV/s**: solved 3BV divided by the game time.
+//! * **IOE** (Index of Efficiency): solved 3BV divided by all clicks.
+//! * **Correctness**: the share of clicks that changed the board.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, Board, Cell, CellState, Minesweeper, Pos};
+
+/// Figures for one game, computed from its layout, board state and recorded moves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub bbbv: usize,
+    /// Part of the 3BV already cleared: openings with a revealed zero and revealed
+    /// stand-alone cells.
+    pub solved_bbbv: usize,
+    pub left_clicks: usize,
+    pub right_clicks: usize,
+    /// Primary and secondary clicks on revealed numbers.
+    pub chord_clicks: usize,
+    /// Clicks that changed the board.
+    pub effective_clicks: usize,
+    pub time: Duration,
+}
+impl Metrics {
+    /// Returns `None` while mines are not placed yet.
+    pub fn from_game(game: &Minesweeper) -> Option<Self> {
+        if game.first_click && !game.layout_fixed {
+            return None;
+        }
+        let (bbbv, solved_bbbv) = bbbv(game);
+        let mut metrics = Self {bbbv, solved_bbbv, time: game.timer.elapsed(), ..Self::default()};
+        for m in &game.moves {
+            match m.action {
+                Action::Reveal => metrics.left_clicks += 1,
+                Action::Flag => metrics.right_clicks += 1,
+                Action::Chord | Action::FlagChord => metrics.chord_clicks += 1,
+            }
+            metrics.effective_clicks += m.effective as usize;
+        }
+        Some(metrics)
+    }
+    pub fn clicks(&self) -> usize {
+        self.left_clicks + self.right_clicks + self.chord_clicks
+    }
+    pub fn bbbv_per_second(&self) -> f64 {
+        let secs = self.time.as_secs_f64();
+        if secs > 0.0 {self.solved_bbbv as f64 / secs} else {0.0}
+    }
+    pub fn ioe(&self) -> f64 {
+        if self.clicks() > 0 {self.solved_bbbv as f64 / self.clicks() as f64} else {0.0}
+    }
+    pub fn correctness(&self) -> f64 {
+        if self.clicks() > 0 {self.effective_clicks as f64 / self.clicks() as f64} else {0.0}
+    }
+}
+
+/// Total and solved 3BV of a board whose mines are placed. Cells shown by losing the game do
+/// not count as solved.
+pub fn bbbv(game: &Minesweeper) -> (usize, usize) {
+    let grid = &game.grid;
+    let is_zero = |pos: Pos| !grid[pos].is_mine() && grid[pos].adjacent_mines == 0;
+    let opened = |cell: &Cell| cell.state == CellState::Revealed && !cell.exposed;
+    let mut covered = Board::new(grid.width(), grid.height(), false);
+    let (mut total, mut solved) = (0, 0);
+    for pos in grid.positions() {
+        if covered[pos] || !is_zero(pos) {
+            continue;
+        }
+        total += 1;
+        let mut open = false;
+        let mut queue = VecDeque::from([pos]);
+        covered[pos] = true;
+        while let Some(pos) = queue.pop_front() {
+            open |= opened(&grid[pos]);
+            for n in grid.neighbors(pos) {
+                if !covered[n] {
+                    covered[n] = true;
+                    if is_zero(n) {
+                        queue.push_back(n);
+                    }
+                }
+            }
+        }
+        solved += open as usize;
+    }
+    for (_, cell) in grid.enumerate().filter(|&(pos, cell)| !covered[pos] && !cell.is_mine()) {
+        total += 1;
+        solved += opened(cell) as usize;
+    }
+    (total, solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameConfig;
+
+    #[test]
+    fn losing_keeps_solved_bbbv() {
+        // Two openings either side of the mine.
+        let config = GameConfig {width: 5, height: 1, ..GameConfig::default()};
+        let mut game = Minesweeper::with_layout(config, &[Pos::new(2, 0)]);
+        game.play(Action::Reveal, Pos::new(0, 0));
+        let before = Metrics::from_game(&game).unwrap();
+        assert_eq!((before.bbbv, before.solved_bbbv), (2, 1));
+        game.play(Action::Reveal, Pos::new(2, 0));
+        assert!(game.game_over);
+        let after = Metrics::from_game(&game).unwrap();
+        assert_eq!((after.bbbv, after.solved_bbbv), (before.bbbv, before.solved_bbbv));
+    }
+}
@@ -0,0 +1,103 @@
+//! Versioned replay files: a mine layout plus every recorded input.
+//!
+//! Replays are stored as JSON:
+//!
+//! ```json
+//! {"version": 1, "config": {...}, "seed": 42, "mines": [[3, 0], [7, 2]],
+//!  "moves": [{"action": "Reveal", "x": 4, "y": 4, "time": {"secs": 0, "nanos": 0}}]}
+//! ```
+//!
+//! `mines` lists `[x, y]` positions, so a replay plays back identically even if mine
+//! generation changes. `moves` uses the [`Move`] format recorded by [`Minesweeper::play`].
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout;
+use crate::{GameConfig, Minesweeper, Move, Pos};
+
+/// Format version written by this crate. Files with a newer version are rejected.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A recorded game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub config: GameConfig,
+    /// Seed the original game was generated from; informational only.
+    pub seed: u64,
+    pub mines: Vec<Pos>,
+    pub moves: Vec<Move>,
+}
+impl Replay {
+    /// Captures a game's layout and moves. Returns `None` if no move was made yet.
+    pub fn from_game(game: &Minesweeper) -> Option<Self> {
+        if game.first_click || game.moves.is_empty() {
+            return None;
+        }
+        Some(Self {
+            version: REPLAY_VERSION,
+            config: game.config.clone(),
+            seed: game.seed,
+            mines: game.mine_positions(),
+            moves: game.moves.clone(),
+        })
+    }
+    /// The board after the first `step` moves have been played.
+    pub fn position(&self, step: usize) -> Minesweeper {
+        let mut game = Minesweeper::with_layout(self.config.clone(), &self.mines);
+        game.seed = self.seed;
+        for m in &self.moves[..step.min(self.moves.len())] {
+            game.play(m.action, m.pos());
+        }
+        game
+    }
+    /// Time of the last move.
+    pub fn duration(&self) -> Duration {
+        self.moves.last().map_or(Duration::ZERO, |m| m.time)
+    }
+    /// Serializes the replay in the format described in the [module docs](self).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("replays are always serializable")
+    }
+    /// Parses a replay, rejecting unknown versions, moves outside the board and mines that
+    /// [`Layout::from_json`](crate::layout::Layout::from_json) would reject.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let replay: Self = serde_json::from_str(json)?;
+        if replay.version > REPLAY_VERSION {
+            return Err(serde::de::Error::custom(format!("unsupported replay version {}", replay.version)));
+        }
+        layout::check_mines(&replay.config, &replay.mines).map_err(serde::de::Error::custom)?;
+        if !replay.moves.iter().all(|m| layout::inside(&replay.config, m.pos())) {
+            return Err(serde::de::Error::custom("moves outside the board"));
+        }
+        Ok(replay)
+    }
+    /// Writes the replay to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+    /// Reads a replay saved with [`save`](Self::save).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_json() {
+        let json = |mines: &str, x: usize| format!(r#"{{"version": 1, "config": {{"width": 2, "height": 2}}, "seed": 0, "mines": {mines},
+            "moves": [{{"action": "Reveal", "x": {x}, "y": 0, "time": {{"secs": 0, "nanos": 0}}}}]}}"#);
+        assert!(Replay::from_json(&json("[[1, 1]]", 0)).is_ok());
+        assert!(Replay::from_json(&json("[[1, 1]]", 2)).is_err());
+        assert!(Replay::from_json(&json("[[2, 1]]", 0)).is_err());
+        let crowded = format!("[{}]", vec!["[1, 1]"; 256].join(", "));
+        assert!(Replay::from_json(&json(&crowded, 0)).is_err());
+    }
+}
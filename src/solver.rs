@@ -0,0 +1,697 @@
+//! Logical deductions from the player-visible board.
+//!
+//! The solver only reads what the player can see: revealed numbers, flags, and the total
+//! `mine_count` from the config. Flags are trusted to be correct; a contradictory board simply
+//! yields fewer deductions.
+//!
+//! On boards with several mines per cell, arrangements are weighted the way
+//! [`Minesweeper::place_mines`] draws them: a cell with `k` of `n` possible mines counts
+//! `C(n, k)` times.
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Instant;
+
+use crate::{Board, CellState, Minesweeper, Pos};
+
+/// Upper bound on search nodes spent enumerating one frontier component.
+const ENUMERATION_BUDGET: usize = 1_000_000;
+
+/// Hidden, unflagged cells that are provably safe or provably mined. On multi-mine boards,
+/// `mines` only holds cells proven to carry the full [`mines_per_cell`](crate::GameConfig::mines_per_cell).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Deductions {pub safe: BTreeSet<Pos>, pub mines: BTreeSet<Pos>}
+impl Deductions {
+    /// `true` if nothing could be deduced.
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// A revealed number seen from the player's side: exactly `mines` of `cells` are mines.
+struct Constraint {cells: Vec<Pos>, mines: usize}
+
+fn constraints(game: &Minesweeper) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for (pos, cell) in game.grid.enumerate() {
+        if cell.state != CellState::Revealed || cell.is_mine() {
+            continue;
+        }
+        let mut cells = Vec::new();
+        let mut flagged = 0;
+        for n in game.grid.neighbors(pos) {
+            match game.grid[n].state {
+                CellState::Hidden | CellState::Questioned => cells.push(n),
+                CellState::Flagged => flagged += game.grid[n].flags as usize,
+                CellState::Revealed => {}
+            }
+        }
+        if !cells.is_empty() {
+            cells.sort_unstable();
+            constraints.push(Constraint {cells, mines: (cell.adjacent_mines as usize).saturating_sub(flagged)});
+        }
+    }
+    constraints
+}
+
+/// Applies the single-cell and subset rules once, for cells holding up to `per_cell` mines.
+fn deduce_local(constraints: &[Constraint], per_cell: usize) -> Deductions {
+    let mut deductions = Deductions::default();
+    let mut containing: HashMap<Pos, Vec<usize>> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        if c.mines == 0 {
+            deductions.safe.extend(c.cells.iter().copied());
+        } else if c.mines == c.cells.len() * per_cell {
+            deductions.mines.extend(c.cells.iter().copied());
+        }
+        for &cell in &c.cells {
+            containing.entry(cell).or_default().push(i);
+        }
+    }
+    for a in constraints {
+        let mut checked = HashSet::new();
+        for cell in &a.cells {
+            for &bi in &containing[cell] {
+                let b = &constraints[bi];
+                if !checked.insert(bi) || b.cells.len() <= a.cells.len() || b.mines < a.mines
+                    || !a.cells.iter().all(|c| b.cells.binary_search(c).is_ok()) {
+                    continue;
+                }
+                let rest = b.cells.iter().filter(|c| a.cells.binary_search(c).is_err());
+                if b.mines == a.mines {
+                    deductions.safe.extend(rest);
+                } else if b.mines - a.mines == (b.cells.len() - a.cells.len()) * per_cell {
+                    deductions.mines.extend(rest);
+                }
+            }
+        }
+    }
+    deductions
+}
+
+/// Applies only the single-cell and subset rules. Cheaper than [`solve`] but incomplete.
+pub fn deduce(game: &Minesweeper) -> Deductions {
+    if game.first_click || game.game_over || game.game_won {
+        return Deductions::default();
+    }
+    deduce_local(&constraints(game), per_cell(game))
+}
+
+fn per_cell(game: &Minesweeper) -> usize {
+    game.config.mines_per_cell.max(1) as usize
+}
+
+/// A connected group of frontier cells together with every way its mines can be arranged.
+struct Component {
+    cells: Vec<Pos>,
+    /// `solutions[k]`: number of arrangements with exactly `k` mines; `None` if the search
+    /// ran out of budget.
+    solutions: Option<Vec<f64>>,
+    /// `mine_counts[i][k]`: how many of the `k`-mine arrangements put a mine on `cells[i]`.
+    mine_counts: Vec<Vec<f64>>,
+    /// `full_counts[i][k]`: how many of the `k`-mine arrangements fill `cells[i]` completely.
+    full_counts: Vec<Vec<f64>>,
+}
+
+/// Backtracking search over one component. Cells are visited in breadth-first order so
+/// constraints fill up, and get pruned, as early as possible.
+struct Enumeration {
+    constraints: Vec<(Vec<usize>, usize)>,
+    cell_constraints: Vec<Vec<usize>>,
+    order: Vec<usize>,
+    placed: Vec<usize>,
+    open: Vec<usize>,
+    per_cell: usize,
+    /// `weights[m]`: ways to put `m` mines on one cell, `C(per_cell, m)`.
+    weights: Vec<f64>,
+    assignment: Vec<usize>,
+    solutions: Vec<f64>,
+    mine_counts: Vec<Vec<f64>>,
+    full_counts: Vec<Vec<f64>>,
+    steps: usize,
+}
+impl Enumeration {
+    fn new(cell_count: usize, constraints: Vec<(Vec<usize>, usize)>, per_cell: usize) -> Self {
+        let mut cell_constraints = vec![Vec::new(); cell_count];
+        for (i, (cells, _)) in constraints.iter().enumerate() {
+            for &c in cells {
+                cell_constraints[c].push(i);
+            }
+        }
+        let mut order = Vec::with_capacity(cell_count);
+        let mut seen = vec![false; cell_count];
+        for start in 0..cell_count {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            order.push(start);
+            let mut head = order.len() - 1;
+            while head < order.len() {
+                let c = order[head];
+                head += 1;
+                for &k in &cell_constraints[c] {
+                    for &n in &constraints[k].0 {
+                        if !seen[n] {
+                            seen[n] = true;
+                            order.push(n);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            placed: vec![0; constraints.len()],
+            open: constraints.iter().map(|(cells, _)| cells.len()).collect(),
+            constraints,
+            cell_constraints,
+            order,
+            per_cell,
+            weights: (0..=per_cell).scan(1.0, |c, m| {
+                let weight = *c;
+                *c *= (per_cell - m) as f64 / (m + 1) as f64;
+                Some(weight)
+            }).collect(),
+            assignment: vec![0; cell_count],
+            solutions: vec![0.0; cell_count * per_cell + 1],
+            mine_counts: vec![vec![0.0; cell_count * per_cell + 1]; cell_count],
+            full_counts: vec![vec![0.0; cell_count * per_cell + 1]; cell_count],
+            steps: 0,
+        }
+    }
+    /// Returns `false` if the budget ran out. `weight` is the number of ways to realize the
+    /// cells assigned so far.
+    fn search(&mut self, depth: usize, mines: usize, weight: f64) -> bool {
+        self.steps += 1;
+        if self.steps > ENUMERATION_BUDGET {
+            return false;
+        }
+        if depth == self.order.len() {
+            self.solutions[mines] += weight;
+            for (c, &count) in self.assignment.iter().enumerate() {
+                if count > 0 {
+                    self.mine_counts[c][mines] += weight;
+                }
+                if count == self.per_cell {
+                    self.full_counts[c][mines] += weight;
+                }
+            }
+            return true;
+        }
+        let c = self.order[depth];
+        for count in 0..=self.per_cell {
+            let fits = self.cell_constraints[c].iter().all(|&k| {
+                let placed = self.placed[k] + count;
+                let target = self.constraints[k].1;
+                placed <= target && placed + (self.open[k] - 1) * self.per_cell >= target
+            });
+            if !fits {
+                continue;
+            }
+            for &k in &self.cell_constraints[c] {
+                self.placed[k] += count;
+                self.open[k] -= 1;
+            }
+            self.assignment[c] = count;
+            let finished = self.search(depth + 1, mines + count, weight * self.weights[count]);
+            self.assignment[c] = 0;
+            for &k in &self.cell_constraints[c] {
+                self.placed[k] -= count;
+                self.open[k] += 1;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Splits the frontier into independent components and enumerates each one.
+fn components(constraints: &[Constraint], per_cell: usize) -> Vec<Component> {
+    let mut index: HashMap<Pos, usize> = HashMap::new();
+    let mut cells = Vec::new();
+    for c in constraints {
+        for &cell in &c.cells {
+            index.entry(cell).or_insert_with(|| {
+                cells.push(cell);
+                cells.len() - 1
+            });
+        }
+    }
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..cells.len()).collect();
+    for c in constraints {
+        let first = find(&mut parent, index[&c.cells[0]]);
+        for cell in &c.cells[1..] {
+            let other = find(&mut parent, index[cell]);
+            parent[other] = first;
+        }
+    }
+    let mut groups: HashMap<usize, (Vec<usize>, Vec<&Constraint>)> = HashMap::new();
+    for i in 0..cells.len() {
+        groups.entry(find(&mut parent, i)).or_default().0.push(i);
+    }
+    for c in constraints {
+        groups.get_mut(&find(&mut parent, index[&c.cells[0]])).unwrap().1.push(c);
+    }
+    let mut groups: Vec<_> = groups.into_values().collect();
+    groups.sort_unstable_by_key(|(members, _)| members[0]);
+    groups.into_iter().map(|(members, group_constraints)| {
+        let local: HashMap<usize, usize> = members.iter().enumerate().map(|(l, &g)| (g, l)).collect();
+        let local_constraints = group_constraints.iter()
+            .map(|c| (c.cells.iter().map(|cell| local[&index[cell]]).collect(), c.mines))
+            .collect();
+        let mut enumeration = Enumeration::new(members.len(), local_constraints, per_cell);
+        let finished = enumeration.search(0, 0, 1.0);
+        Component {
+            cells: members.iter().map(|&g| cells[g]).collect(),
+            solutions: finished.then_some(enumeration.solutions),
+            mine_counts: enumeration.mine_counts,
+            full_counts: enumeration.full_counts,
+        }
+    }).collect()
+}
+
+/// Everything the solver knows about the current position.
+struct Analysis {
+    /// Results of the local rules, already substituted into `components`.
+    local: Deductions,
+    components: Vec<Component>,
+    /// Hidden cells not adjacent to any revealed number.
+    interior: Vec<Pos>,
+    /// Mines not yet accounted for by flags or `local.mines`.
+    remaining: usize,
+    /// Most mines one cell can hold.
+    per_cell: usize,
+}
+
+fn analyze(game: &Minesweeper) -> Analysis {
+    let per_cell = per_cell(game);
+    let constraints = constraints(game);
+    let local = deduce_local(&constraints, per_cell);
+    let reduced: Vec<Constraint> = constraints.into_iter().filter_map(|c| {
+        let mines = c.mines.saturating_sub(c.cells.iter().filter(|cell| local.mines.contains(cell)).count() * per_cell);
+        let cells: Vec<_> = c.cells.into_iter()
+            .filter(|cell| !local.mines.contains(cell) && !local.safe.contains(cell))
+            .collect();
+        (!cells.is_empty()).then_some(Constraint {cells, mines})
+    }).collect();
+    let components = components(&reduced, per_cell);
+    let frontier: HashSet<Pos> = components.iter().flat_map(|c| c.cells.iter().copied()).collect();
+    let mut interior = Vec::new();
+    let mut flagged = 0;
+    for (pos, cell) in game.grid.enumerate() {
+        match cell.state {
+            CellState::Flagged => flagged += cell.flags as usize,
+            CellState::Hidden | CellState::Questioned if !frontier.contains(&pos) && !local.safe.contains(&pos) && !local.mines.contains(&pos) => {
+                interior.push(pos);
+            }
+            _ => {}
+        }
+    }
+    let remaining = game.config.mine_count.saturating_sub(flagged + local.mines.len() * per_cell);
+    Analysis {local, components, interior, remaining, per_cell}
+}
+
+/// Which mine counts each component can take, given that all components together with the
+/// interior must account for exactly `remaining` mines.
+fn feasible_counts(analysis: &Analysis) -> Vec<Vec<bool>> {
+    let possible: Vec<Vec<bool>> = analysis.components.iter().map(|c| match &c.solutions {
+        Some(solutions) => solutions.iter().map(|&s| s > 0.0).collect(),
+        None => vec![true; c.cells.len() * analysis.per_cell + 1],
+    }).collect();
+    let low = analysis.remaining.saturating_sub(analysis.interior.len() * analysis.per_cell);
+    let high = analysis.remaining;
+    (0..possible.len()).map(|skip| {
+        let others: Vec<&Vec<bool>> = possible.iter().enumerate().filter(|&(i, _)| i != skip).map(|(_, p)| p).collect();
+        let sums = reachable_sums(&others, high);
+        possible[skip].iter().enumerate().map(|(k, &possible)| {
+            possible && k <= high && (low.saturating_sub(k)..=high - k).any(|s| sums[s])
+        }).collect()
+    }).collect()
+}
+
+/// `sums[s]` is `true` if picking one allowed count from each of `counts` can total `s`.
+fn reachable_sums(counts: &[&Vec<bool>], limit: usize) -> Vec<bool> {
+    let mut sums = vec![false; limit + 1];
+    sums[0] = true;
+    for allowed in counts {
+        let mut next = vec![false; limit + 1];
+        for s in (0..=limit).filter(|&s| sums[s]) {
+            for k in (0..allowed.len()).filter(|&k| allowed[k] && s + k <= limit) {
+                next[s + k] = true;
+            }
+        }
+        sums = next;
+    }
+    sums
+}
+
+/// Returns every hidden, unflagged cell whose content follows from the visible board.
+///
+/// Single-cell and subset rules run first; the remaining frontier is split into independent
+/// components whose arrangements are enumerated exhaustively and checked against the global
+/// mine count. Cells away from the frontier are only decided by that count.
+pub fn solve(game: &Minesweeper) -> Deductions {
+    if game.first_click || game.game_over || game.game_won {
+        return Deductions::default();
+    }
+    let analysis = analyze(game);
+    let feasible = feasible_counts(&analysis);
+    let mut deductions = analysis.local.clone();
+    for (component, feasible) in analysis.components.iter().zip(&feasible) {
+        let Some(solutions) = &component.solutions else {
+            continue;
+        };
+        let ks: Vec<usize> = (0..feasible.len()).filter(|&k| feasible[k]).collect();
+        if ks.is_empty() {
+            continue;
+        }
+        for (i, &cell) in component.cells.iter().enumerate() {
+            if ks.iter().all(|&k| component.mine_counts[i][k] == 0.0) {
+                deductions.safe.insert(cell);
+            } else if ks.iter().all(|&k| component.full_counts[i][k] == solutions[k]) {
+                deductions.mines.insert(cell);
+            }
+        }
+    }
+    if !analysis.interior.is_empty() {
+        let capacity = analysis.interior.len() * analysis.per_cell;
+        let low = analysis.remaining.saturating_sub(capacity);
+        let sums = reachable_sums(&feasible.iter().collect::<Vec<_>>(), analysis.remaining);
+        let totals: Vec<usize> = (low..=analysis.remaining).filter(|&s| sums[s]).collect();
+        if totals == [analysis.remaining] {
+            deductions.safe.extend(analysis.interior.iter().copied());
+        } else if totals == [low] && analysis.remaining - low == capacity {
+            deductions.mines.extend(analysis.interior.iter().copied());
+        }
+    }
+    deductions
+}
+
+/// Exact probability that each hidden, unflagged cell is a mine.
+///
+/// Every arrangement of the remaining mines that agrees with the visible numbers, the flags and
+/// `mine_count` is counted as equally likely. Other cells, and every cell before the first move
+/// or after the game ends, are `None`. If a frontier component is too large to enumerate within
+/// the solver's budget, its cells are treated like unconstrained cells, so their values are
+/// approximate.
+pub fn probabilities(game: &Minesweeper) -> Board<Option<f64>> {
+    let mut result = game.grid.map(|_| None);
+    if game.first_click || game.game_over || game.game_won {
+        return result;
+    }
+    let analysis = analyze(game);
+    let mut interior = analysis.interior.clone();
+    // Each component's counts are scaled so its largest entry is 1; a common factor per
+    // component cancels out of every probability and keeps the products in range.
+    let mut enumerated = Vec::new();
+    for component in &analysis.components {
+        match &component.solutions {
+            Some(solutions) => {
+                let scale = solutions.iter().cloned().fold(0.0, f64::max);
+                if scale == 0.0 {
+                    return result;
+                }
+                let solutions: Vec<f64> = solutions.iter().map(|s| s / scale).collect();
+                let mine_counts: Vec<Vec<f64>> = component.mine_counts.iter()
+                    .map(|counts| counts.iter().map(|m| m / scale).collect())
+                    .collect();
+                enumerated.push((&component.cells, solutions, mine_counts));
+            }
+            None => interior.extend(component.cells.iter().copied()),
+        }
+    }
+    let remaining = analysis.remaining;
+    let per_cell = analysis.per_cell;
+    // Unconstrained cells hold `free` mine slots in total.
+    let free = interior.len() * per_cell;
+    let min_free_mines = remaining.saturating_sub(enumerated.iter().map(|(cells, _, _)| cells.len() * per_cell).sum());
+    if min_free_mines > free {
+        return result;
+    }
+    // `free_weight[j]` ∝ C(free, j): ways to place `j` mines among the unconstrained slots.
+    let max_free_mines = remaining.min(free);
+    let mut log_weight = vec![0.0; max_free_mines + 1];
+    // `empty[j]`: chance that a given unconstrained cell gets none of `j` mines,
+    // C(free - per_cell, j) / C(free, j).
+    let mut empty = vec![1.0; max_free_mines + 1];
+    for j in 0..max_free_mines {
+        log_weight[j + 1] = log_weight[j] + ((free - j) as f64 / (j + 1) as f64).ln();
+        empty[j + 1] = empty[j] * free.saturating_sub(per_cell + j) as f64 / (free - j) as f64;
+    }
+    let top = log_weight[min_free_mines..].iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let free_weight = |j: usize| if j < min_free_mines || j > max_free_mines {0.0} else {(log_weight[j] - top).exp()};
+    let product = |skip: Option<usize>| {
+        let mut poly = vec![1.0];
+        for (i, (_, solutions, _)) in enumerated.iter().enumerate() {
+            if Some(i) == skip {
+                continue;
+            }
+            let mut next = vec![0.0; poly.len() + solutions.len() - 1];
+            for (a, &pa) in poly.iter().enumerate() {
+                for (b, &sb) in solutions.iter().enumerate() {
+                    next[a + b] += pa * sb;
+                }
+            }
+            poly = next;
+        }
+        poly
+    };
+    let all = product(None);
+    let weighted = |poly: &[f64], taken: usize, f: &dyn Fn(usize) -> f64| -> f64 {
+        poly.iter().enumerate()
+            .filter(|&(s, _)| s + taken <= remaining)
+            .map(|(s, &p)| p * f(remaining - s - taken))
+            .sum()
+    };
+    let total = weighted(&all, 0, &free_weight);
+    if total <= 0.0 {
+        return result;
+    }
+    for &pos in &analysis.local.safe {
+        result[pos] = Some(0.0);
+    }
+    for &pos in &analysis.local.mines {
+        result[pos] = Some(1.0);
+    }
+    if free > 0 {
+        let free_mines = weighted(&all, 0, &|j| free_weight(j) * (1.0 - empty.get(j).unwrap_or(&1.0)));
+        for &pos in &interior {
+            result[pos] = Some(free_mines / total);
+        }
+    }
+    for (i, (cells, _, mine_counts)) in enumerated.iter().enumerate() {
+        let others = product(Some(i));
+        for (&pos, counts) in cells.iter().zip(mine_counts) {
+            let mines: f64 = counts.iter().enumerate()
+                .filter(|&(_, &m)| m > 0.0)
+                .map(|(k, &m)| m * weighted(&others, k, &free_weight))
+                .sum();
+            result[pos] = Some((mines / total).clamp(0.0, 1.0));
+        }
+    }
+    result
+}
+
+/// The hidden cell least likely to be a mine in a [`probabilities`] board; ties go to the
+/// first cell in row order.
+pub fn safest_cell(probabilities: &Board<Option<f64>>) -> Option<Pos> {
+    let mut best: Option<(Pos, f64)> = None;
+    for (pos, p) in probabilities.enumerate() {
+        if let Some(p) = *p && best.is_none_or(|(_, b)| p < b) {
+            best = Some((pos, p));
+        }
+    }
+    best.map(|(pos, _)| pos)
+}
+
+/// A suggested next move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hint {
+    /// The cell is provably safe to open.
+    Safe(Pos),
+    /// The cell is provably a mine (a full cell on multi-mine boards) and can be flagged.
+    Mine(Pos),
+    /// No move is certain; this cell has the lowest probability of being a mine.
+    Guess(Pos, f64),
+}
+
+/// Suggests a provably safe cell, otherwise a provable mine, otherwise the safest guess.
+/// Returns `None` before the first move and once the game is over.
+pub fn hint(game: &Minesweeper) -> Option<Hint> {
+    let deductions = solve(game);
+    if let Some(&pos) = deductions.safe.first() {
+        return Some(Hint::Safe(pos));
+    }
+    if let Some(&pos) = deductions.mines.first() {
+        return Some(Hint::Mine(pos));
+    }
+    let probabilities = probabilities(game);
+    safest_cell(&probabilities).map(|pos| Hint::Guess(pos, probabilities[pos].unwrap_or(1.0)))
+}
+
+/// Plays the already-placed layout from `start` using only deductions, and reports whether
+/// every safe cell can be opened without guessing. Gives up with `false` at `deadline`.
+pub(crate) fn solvable_from(game: &Minesweeper, start: Pos, deadline: Instant) -> bool {
+    let mut sim = game.clone();
+    sim.first_click = false;
+    sim.reveal_cell(start);
+    loop {
+        if sim.game_over || Instant::now() >= deadline {
+            return false;
+        }
+        if sim.game_won || sim.grid.iter().all(|c| c.is_mine() || c.state == CellState::Revealed) {
+            return true;
+        }
+        let mut deductions = deduce(&sim);
+        if deductions.is_empty() {
+            deductions = solve(&sim);
+        }
+        if deductions.is_empty() {
+            return false;
+        }
+        for pos in deductions.mines {
+            sim.grid[pos].state = CellState::Flagged;
+            sim.grid[pos].flags = per_cell(game) as u8;
+        }
+        for pos in deductions.safe {
+            sim.reveal_cell(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::GameConfig;
+
+    /// A game in progress on `rows`: `*` is a hidden mine, `.` a hidden safe cell and any other
+    /// character a revealed safe cell.
+    fn game(rows: &[&str]) -> Minesweeper {
+        let config = GameConfig {width: rows[0].len(), height: rows.len(), ..GameConfig::default()};
+        let cells = || rows.iter().enumerate().flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| (Pos::new(x, y), c)));
+        let mines: Vec<Pos> = cells().filter(|&(_, c)| c == '*').map(|(pos, _)| pos).collect();
+        let mut game = Minesweeper::with_layout(config, &mines);
+        for (pos, _) in cells().filter(|&(_, c)| c != '*' && c != '.') {
+            game.grid[pos].state = CellState::Revealed;
+        }
+        game.first_click = false;
+        game
+    }
+
+    fn set(cells: &[(usize, usize)]) -> BTreeSet<Pos> {
+        cells.iter().map(|&(x, y)| Pos::new(x, y)).collect()
+    }
+
+    #[test]
+    fn one_one_clears_the_third_cell() {
+        // The left 1 puts its mine among the first two cells, so the middle 1 has none left
+        // for the third.
+        let game = game(&[".*.", "111"]);
+        assert!(deduce(&game).safe.contains(&Pos::new(2, 0)));
+        assert_eq!(solve(&game), Deductions {safe: set(&[(0, 0), (2, 0)]), mines: set(&[(1, 0)])});
+    }
+
+    #[test]
+    fn one_two_one_has_mines_under_the_ones() {
+        let game = game(&[".*.*.", "11211"]);
+        assert_eq!(solve(&game), Deductions {safe: set(&[(0, 0), (2, 0), (4, 0)]), mines: set(&[(1, 0), (3, 0)])});
+    }
+
+    #[test]
+    fn mine_count_decides_the_interior() {
+        // The 1 leaves a 50/50 between its neighbors; the cells beyond them are decided by
+        // how many mines are left.
+        let safe_interior = game(&["*1..."]);
+        assert_eq!(solve(&safe_interior), Deductions {safe: set(&[(3, 0), (4, 0)]), mines: BTreeSet::new()});
+        let mined_interior = game(&["*1.**"]);
+        assert_eq!(solve(&mined_interior), Deductions {safe: BTreeSet::new(), mines: set(&[(3, 0), (4, 0)])});
+    }
+
+    /// Mine probabilities and deductions from every arrangement of mines over the hidden cells,
+    /// weighted like [`probabilities`] and [`solve`] weight them.
+    fn brute_force(game: &Minesweeper) -> (HashMap<Pos, f64>, Deductions) {
+        let per_cell = per_cell(game);
+        let hidden: Vec<Pos> = game.grid.positions().filter(|&pos| game.grid[pos].state.is_hidden()).collect();
+        let revealed: Vec<Pos> = game.grid.positions().filter(|&pos| game.grid[pos].state == CellState::Revealed).collect();
+        let mut counts = vec![0; hidden.len()];
+        let (mut total, mut mined, mut full) = (0.0, vec![0.0; hidden.len()], vec![0.0; hidden.len()]);
+        loop {
+            let fits = counts.iter().sum::<usize>() == game.config.mine_count
+                && revealed.iter().all(|&pos| {
+                    let around: usize = hidden.iter().zip(&counts).filter(|&(&h, _)| game.grid.neighbors(pos).any(|n| n == h)).map(|(_, &k)| k).sum();
+                    around == game.grid[pos].adjacent_mines as usize
+                });
+            if fits {
+                let weight: f64 = counts.iter().map(|&k| binomial(per_cell, k)).product();
+                total += weight;
+                for (i, &k) in counts.iter().enumerate() {
+                    mined[i] += if k > 0 {weight} else {0.0};
+                    full[i] += if k == per_cell {weight} else {0.0};
+                }
+            }
+            let Some(i) = counts.iter().position(|&k| k < per_cell) else {
+                break;
+            };
+            counts[..i].fill(0);
+            counts[i] += 1;
+        }
+        let mut deductions = Deductions::default();
+        let mut probabilities = HashMap::new();
+        for (i, &pos) in hidden.iter().enumerate() {
+            probabilities.insert(pos, mined[i] / total);
+            if mined[i] == 0.0 {
+                deductions.safe.insert(pos);
+            } else if full[i] == total {
+                deductions.mines.insert(pos);
+            }
+        }
+        (probabilities, deductions)
+    }
+
+    fn binomial(n: usize, k: usize) -> f64 {
+        (0..k).map(|i| (n - i) as f64 / (i + 1) as f64).product()
+    }
+
+    #[test]
+    fn matches_brute_force_on_small_boards() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut compared = 0;
+        for per_cell in [1, 2] {
+            for _ in 0..60 {
+                let config = GameConfig {width: 4, height: 3, mine_count: rng.random_range(1..=5), mines_per_cell: per_cell, ..GameConfig::default()};
+                let mut game = Minesweeper::with_seed(config, rng.random());
+                game.reveal_cell(Pos::new(0, 0));
+                for _ in 0..rng.random_range(0..3) {
+                    let pos = Pos::new(rng.random_range(0..4), rng.random_range(0..3));
+                    if !game.grid[pos].is_mine() {
+                        game.reveal_cell(pos);
+                    }
+                }
+                if game.game_won {
+                    continue;
+                }
+                compared += 1;
+                let (expected, deductions) = brute_force(&game);
+                assert_eq!(solve(&game), deductions, "seed {}", game.seed);
+                for (pos, p) in probabilities(&game).enumerate() {
+                    match (p, expected.get(&pos)) {
+                        (Some(p), Some(e)) => assert!((p - e).abs() < 1e-9, "seed {} at {pos:?}: {p} != {e}", game.seed),
+                        (None, None) => {}
+                        _ => panic!("seed {} at {pos:?}: {p:?} != {:?}", game.seed, expected.get(&pos)),
+                    }
+                }
+            }
+        }
+        assert!(compared > 60, "only {compared} boards were still in play");
+    }
+}
@@ -0,0 +1,112 @@
+//! Win/loss statistics kept per board configuration.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::Metrics;
+use crate::{GameConfig, Minesweeper};
+
+/// Number of recent games kept per config in [`Record::history`].
+pub const HISTORY_LEN: usize = 50;
+
+/// Summary of one recorded game.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub won: bool,
+    pub hints_used: u32,
+    /// Mines hit, including the one that ended a lost game.
+    #[serde(default)]
+    pub lives_lost: u32,
+    pub metrics: Metrics,
+}
+
+/// Results for one [`GameConfig`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub played: u32,
+    pub won: u32,
+    /// Wins in a row up to the most recent game.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub best_time: Option<Duration>,
+    /// Sum of all winning times, for [`average_time`](Self::average_time).
+    pub total_win_time: Duration,
+    /// Highest 3BV/s of a won game.
+    #[serde(default)]
+    pub best_bbbv_per_second: Option<f64>,
+    /// The last [`HISTORY_LEN`] games, oldest first.
+    #[serde(default)]
+    pub history: Vec<GameResult>,
+}
+impl Record {
+    /// Mean time of the won games.
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.won > 0).then(|| self.total_win_time / self.won)
+    }
+    /// Fraction of games won, in `0.0..=1.0`.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {0.0} else {self.won as f64 / self.played as f64}
+    }
+}
+
+/// Question marks do not change the board, so games with and without them share a record.
+fn record_key(config: &GameConfig) -> GameConfig {
+    GameConfig {question_marks: false, ..config.clone()}
+}
+
+/// All records, keyed by the config they were played with.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {records: Vec<(GameConfig, Record)>}
+impl Statistics {
+    /// Adds a finished or abandoned game. Games that never started (no move made), practice
+    /// games, games with undone moves and games on a fixed layout are ignored; anything not won
+    /// counts as a loss. A no-guess game whose board could not be made guess-free counts as an
+    /// ordinary game.
+    pub fn record(&mut self, game: &Minesweeper) {
+        if game.first_click || game.practice || game.undone || game.layout_fixed {
+            return;
+        }
+        let Some(metrics) = Metrics::from_game(game) else {
+            return;
+        };
+        let mut key = record_key(&game.config);
+        key.no_guess &= !game.no_guess_failed;
+        let record = self.entry(&key);
+        record.played += 1;
+        record.history.push(GameResult {won: game.game_won, hints_used: game.hints_used, lives_lost: game.lives_lost, metrics});
+        if record.history.len() > HISTORY_LEN {
+            record.history.remove(0);
+        }
+        if game.game_won {
+            let rate = metrics.bbbv_per_second();
+            record.best_bbbv_per_second = Some(record.best_bbbv_per_second.map_or(rate, |best| best.max(rate)));
+            let time = game.timer.elapsed();
+            record.won += 1;
+            record.current_streak += 1;
+            record.best_streak = record.best_streak.max(record.current_streak);
+            record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+            record.total_win_time += time;
+        } else {
+            record.current_streak = 0;
+        }
+    }
+    /// The record for `config`, if any game was played with it.
+    pub fn get(&self, config: &GameConfig) -> Option<&Record> {
+        let config = record_key(config);
+        self.records.iter().find(|(c, _)| *c == config).map(|(_, record)| record)
+    }
+    /// All records in the order their configs were first played.
+    pub fn iter(&self) -> impl Iterator<Item = (&GameConfig, &Record)> {
+        self.records.iter().map(|(config, record)| (config, record))
+    }
+    fn entry(&mut self, config: &GameConfig) -> &mut Record {
+        let index = match self.records.iter().position(|(c, _)| c == config) {
+            Some(index) => index,
+            None => {
+                self.records.push((config.clone(), Record::default()));
+                self.records.len() - 1
+            }
+        };
+        &mut self.records[index].1
+    }
+}